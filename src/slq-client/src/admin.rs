@@ -10,20 +10,52 @@ use solana_sdk::signature::{read_keypair_file, Keypair, Signer};
 use solana_sdk::transaction::Transaction;
 use std::convert::TryFrom;
 use std::convert::TryInto;
+use std::path::PathBuf;
 use std::str::FromStr;
 use structopt::StructOpt;
 
 use borsh::BorshDeserialize;
-use slq::admin::{AddAdminAccountAdmin, ChangeApprovalThresholdAdmin, RemoveAdminAccountAdmin};
+use slq::admin::{
+    invoke_action, AcceptAdminAccountAdmin, ChangeApprovalThresholdAdmin, InvokeAdmin,
+    NominateAdminAccountAdmin, PauseAdmin, RemoveAdminAccountAdmin, RotateAdminKeyAdmin,
+    SetAdminConfigAdmin, SetApprovalPolicyAdmin, SetGuardiansAdmin, SetRecoveryConfigAdmin,
+    UnpauseAdmin,
+};
 use slq::init;
 use slq::init::make_instance_pda;
-use slq::state::{AdminConfig, SlqInstance, MAX_ADMIN_ACCOUNTS};
+use slq::state::{
+    AdminAccount, AdminConfig, AdminKind, ApprovalAction, ApprovalPolicy, RecoveryConfig, Roles,
+    SlqAccount, SlqInstance, StoredInstruction,
+};
+use slq::validate;
+
+use crate::init::{parse_admin_account, parse_admin_pubkey};
+use crate::multisig_tx::{decompile_instructions, load_tx};
 
 #[derive(StructOpt, Debug)]
 pub enum AdminCommand {
     ChangeApprovalThreshold(ChangeApprovalThresholdAdminCommand),
-    AddAdminAccount(AddAdminAccountAdminCommand),
+    /// Nominate an admin account, which joins once it runs `admin accept`.
+    Nominate(NominateAdminCommand),
+    /// Accept a nomination, as the nominated account.
+    Accept(AcceptAdminCommand),
     RemoveAdminAccount(RemoveAdminAccountAdminCommand),
+    /// Replace your own admin key with another, keeping its weight and roles.
+    RotateKey(RotateKeyAdminCommand),
+    /// Invoke the instruction in a transaction file, signed by the instance's authority.
+    Invoke(InvokeAdminCommand),
+    /// Set the approval thresholds of specific actions, 0 to use the approval threshold.
+    SetApprovalPolicy(SetApprovalPolicyAdminCommand),
+    /// Replace the admin accounts and approval threshold in one instruction.
+    SetAdminConfig(SetAdminConfigAdminCommand),
+    /// Pause vault withdrawals and proposal execution, as a guardian.
+    Pause(PauseAdminCommand),
+    /// Lift a pause, which needs the highest approval threshold.
+    Unpause(UnpauseAdminCommand),
+    /// Set the accounts that may replace the admins once they have been inactive.
+    SetRecoveryConfig(SetRecoveryConfigAdminCommand),
+    /// Set the accounts outside the admin set that may pause the instance.
+    SetGuardians(SetGuardiansAdminCommand),
 }
 
 #[derive(StructOpt, Debug)]
@@ -33,9 +65,22 @@ pub struct ChangeApprovalThresholdAdminCommand {
 }
 
 #[derive(StructOpt, Debug)]
-pub struct AddAdminAccountAdminCommand {
+pub struct NominateAdminCommand {
     instance_name: String,
+    /// The account to nominate, or `@INSTANCE_NAME` for another slq instance.
     account: String,
+    /// How much the new admin's approval counts towards the threshold.
+    #[structopt(long, default_value = "1")]
+    weight: u8,
+    /// Comma-separated roles of the new admin:
+    /// proposer, approver, executor, canceller and guardian.
+    #[structopt(long, default_value = "all")]
+    roles: Roles,
+}
+
+#[derive(StructOpt, Debug)]
+pub struct AcceptAdminCommand {
+    instance_name: String,
 }
 
 #[derive(StructOpt, Debug)]
@@ -44,22 +89,134 @@ pub struct RemoveAdminAccountAdminCommand {
     account: String,
 }
 
+#[derive(StructOpt, Debug)]
+pub struct RotateKeyAdminCommand {
+    instance_name: String,
+    /// Keypair file of the new key, which signs the rotation.
+    new_keypair: PathBuf,
+}
+
+#[derive(StructOpt, Debug)]
+pub struct PauseAdminCommand {
+    instance_name: String,
+}
+
+#[derive(StructOpt, Debug)]
+pub struct UnpauseAdminCommand {
+    instance_name: String,
+}
+
+#[derive(StructOpt, Debug)]
+pub struct SetRecoveryConfigAdminCommand {
+    instance_name: String,
+    recovery_accounts: Vec<String>,
+    /// The number of recovery accounts that must sign to start a recovery.
+    #[structopt(long, required_unless = "remove")]
+    threshold: Option<u8>,
+    /// Seconds without an admin action before recovery may start.
+    #[structopt(long, required_unless = "remove")]
+    inactivity_period: Option<i64>,
+    /// Seconds during which any admin may veto a started recovery.
+    #[structopt(long, required_unless = "remove")]
+    timelock: Option<i64>,
+    /// Remove the recovery config instead.
+    #[structopt(long, conflicts_with_all = &["threshold", "inactivity-period", "timelock"])]
+    remove: bool,
+}
+
+#[derive(StructOpt, Debug)]
+pub struct SetGuardiansAdminCommand {
+    instance_name: String,
+    /// The new guardians, none to remove every guardian.
+    guardians: Vec<String>,
+}
+
+#[derive(StructOpt, Debug)]
+pub struct InvokeAdminCommand {
+    instance_name: String,
+    transaction_path: PathBuf,
+}
+
+/// The approval policy is kept as is.
+#[derive(StructOpt, Debug)]
+pub struct SetAdminConfigAdminCommand {
+    instance_name: String,
+    /// The admin weight required to approve an instruction.
+    approval_threshold: u8,
+    /// Admin accounts as `PUBKEY[:WEIGHT[:ROLES]]`,
+    /// weight 1 and all roles if omitted.
+    /// `@INSTANCE_NAME` in place of PUBKEY makes another slq instance an admin.
+    admin_accounts: Vec<String>,
+    /// The most admin accounts the instance may have, the current maximum if omitted.
+    #[structopt(long)]
+    max_admin_accounts: Option<u8>,
+}
+
+/// Thresholds that aren't given keep their current value.
+#[derive(StructOpt, Debug)]
+pub struct SetApprovalPolicyAdminCommand {
+    instance_name: String,
+    /// Threshold for adding and removing admin accounts.
+    #[structopt(long)]
+    admin_set: Option<u8>,
+    /// Threshold for changing the approval threshold or policy.
+    #[structopt(long)]
+    threshold_change: Option<u8>,
+    /// Threshold for vault withdrawals above the vault withdrawal limit.
+    #[structopt(long)]
+    vault_withdrawal: Option<u8>,
+    #[structopt(long)]
+    vault_withdrawal_limit: Option<u64>,
+    /// Threshold for invoking the upgradeable bpf loader.
+    #[structopt(long)]
+    program_upgrade: Option<u8>,
+    /// Threshold for executing other proposals.
+    #[structopt(long)]
+    proposal: Option<u8>,
+    /// Seconds between a proposal meeting its threshold and it becoming executable.
+    ///
+    /// While non-zero, admin commands must be submitted as proposals.
+    #[structopt(long)]
+    execution_delay: Option<i64>,
+}
+
+/// Keypairs that must sign alongside `rent_payer` are pushed to `signers`.
 pub(crate) fn do_command(
     client: &RpcClient,
     program_id: &Pubkey,
     rent_payer: &Pubkey,
+    signers: &mut Vec<Keypair>,
     cmd: AdminCommand,
 ) -> Result<Instruction> {
     match cmd {
         AdminCommand::ChangeApprovalThreshold(cmd) => {
             change_approval_threshold_instruction(client, program_id, rent_payer, cmd)
         }
-        AdminCommand::AddAdminAccount(cmd) => {
-            add_admin_account_instruction(client, program_id, rent_payer, cmd)
+        AdminCommand::Nominate(cmd) => {
+            nominate_admin_instruction(client, program_id, rent_payer, cmd)
         }
+        AdminCommand::Accept(cmd) => accept_admin_instruction(client, program_id, rent_payer, cmd),
         AdminCommand::RemoveAdminAccount(cmd) => {
             remove_admin_account_instruction(client, program_id, rent_payer, cmd)
         }
+        AdminCommand::RotateKey(cmd) => {
+            rotate_key_instruction(client, program_id, rent_payer, signers, cmd)
+        }
+        AdminCommand::Invoke(cmd) => invoke_instruction(client, program_id, rent_payer, cmd),
+        AdminCommand::SetApprovalPolicy(cmd) => {
+            set_approval_policy_instruction(client, program_id, rent_payer, cmd)
+        }
+        AdminCommand::SetAdminConfig(cmd) => {
+            set_admin_config_instruction(client, program_id, rent_payer, cmd)
+        }
+        AdminCommand::Pause(cmd) => pause_instruction(client, program_id, rent_payer, cmd),
+        AdminCommand::Unpause(cmd) => unpause_instruction(client, program_id, rent_payer, cmd),
+        AdminCommand::SetRecoveryConfig(cmd) => {
+            set_recovery_config_instruction(client, program_id, rent_payer, cmd)
+        }
+        AdminCommand::SetGuardians(cmd) => {
+            set_guardians_instruction(client, program_id, rent_payer, cmd)
+        }
     }
 }
 
@@ -71,76 +228,93 @@ fn change_approval_threshold_instruction(
 ) -> Result<Instruction> {
     let (instance_pubkey, _) = make_instance_pda(program_id, &cmd.instance_name);
     let instance_account = client.get_account(&instance_pubkey)?;
-    let slq_instance = SlqInstance::try_from_slice(&instance_account.data)?;
+    let slq_instance = SlqInstance::load(&instance_account.data)?;
+    check_rent_payer_can_approve(&slq_instance, rent_payer, ApprovalAction::ThresholdChange)?;
 
-    let admin_accounts = slq_instance
-        .admin_config
-        .admin_accounts
-        .iter()
-        .filter(|account| **account != Pubkey::default())
-        .copied()
-        .collect::<Vec<Pubkey>>();
-    {
-        let cmd_approval_threshold = usize::from(cmd.approval_threshold);
-        if cmd_approval_threshold == 0 {
-            bail!("approval threshold must be greater than 0");
-        }
-        if cmd_approval_threshold > admin_accounts.len() {
-            bail!(
-                "approval threshold must be less than or equal to {}, the number of administrators",
-                admin_accounts.len()
-            );
-        }
-        if cmd_approval_threshold == admin_accounts.len() {
-            bail!("approval threshold is {} already", cmd.approval_threshold);
-        }
+    let mut admin_config = slq_instance.admin_config.clone();
+    if cmd.approval_threshold == admin_config.approval_threshold {
+        bail!("approval threshold is {} already", cmd.approval_threshold);
     }
+    admin_config.approval_threshold = cmd.approval_threshold;
+    validate::admin_config(&admin_config)?;
 
     ChangeApprovalThresholdAdmin::build_instruction(
         program_id,
         rent_payer,
+        &[*rent_payer],
         cmd.instance_name,
+        slq_instance.config_epoch,
         cmd.approval_threshold,
     )
 }
 
-fn add_admin_account_instruction(
+fn nominate_admin_instruction(
     client: &RpcClient,
     program_id: &Pubkey,
     rent_payer: &Pubkey,
-    cmd: AddAdminAccountAdminCommand,
+    cmd: NominateAdminCommand,
 ) -> Result<Instruction> {
     let (instance_pubkey, _) = make_instance_pda(program_id, &cmd.instance_name);
     let instance_account = client.get_account(&instance_pubkey)?;
-    let slq_instance = SlqInstance::try_from_slice(&instance_account.data)?;
+    let slq_instance = SlqInstance::load(&instance_account.data)?;
+    check_rent_payer_can_approve(&slq_instance, rent_payer, ApprovalAction::AdminSetChange)?;
 
-    let new_admin_account = Pubkey::from_str(&cmd.account)?;
-    let mut admin_accounts = slq_instance
-        .admin_config
-        .admin_accounts
-        .iter()
-        .filter(|account| **account != Pubkey::default())
-        .copied()
-        .collect::<Vec<Pubkey>>();
+    let (new_admin_account, new_admin_kind) = parse_admin_pubkey(program_id, &cmd.account)?;
 
-    if admin_accounts.len() == MAX_ADMIN_ACCOUNTS {
-        bail!(
-            "there are already {} admin accounts, remove one to add a new account",
-            MAX_ADMIN_ACCOUNTS
-        );
-    }
-    if admin_accounts.contains(&new_admin_account) {
-        bail!("account {} already exists", &new_admin_account);
-    }
+    let mut admin_config = slq_instance.admin_config.clone();
+    admin_config.admin_accounts.push(AdminAccount {
+        pubkey: new_admin_account,
+        weight: cmd.weight,
+        roles: cmd.roles,
+        kind: new_admin_kind,
+    });
+    validate::admin_config(&admin_config)?;
 
-    AddAdminAccountAdmin::build_instruction(
+    NominateAdminAccountAdmin::build_instruction(
         program_id,
         rent_payer,
+        &[*rent_payer],
         cmd.instance_name,
+        slq_instance.config_epoch,
         new_admin_account,
+        cmd.weight,
+        cmd.roles,
+        new_admin_kind,
     )
 }
 
+/// `rent_payer` is the nominated account accepting.
+fn accept_admin_instruction(
+    client: &RpcClient,
+    program_id: &Pubkey,
+    rent_payer: &Pubkey,
+    cmd: AcceptAdminCommand,
+) -> Result<Instruction> {
+    let (instance_pubkey, _) = make_instance_pda(program_id, &cmd.instance_name);
+    let instance_account = client.get_account(&instance_pubkey)?;
+    let slq_instance = SlqInstance::load(&instance_account.data)?;
+
+    let nominee = match slq_instance.pending_admin {
+        Some(nominee) if nominee.signer(program_id) == Some(*rent_payer) => nominee,
+        Some(AdminAccount {
+            pubkey,
+            kind: AdminKind::Instance { .. },
+            ..
+        }) => bail!(
+            "instance {} is nominated, it accepts with its authority through a proposal",
+            pubkey
+        ),
+        Some(nominee) => bail!("{} is nominated, not {}", nominee.pubkey, rent_payer),
+        None => bail!("instance has no pending admin"),
+    };
+
+    let mut admin_config = slq_instance.admin_config.clone();
+    admin_config.admin_accounts.push(nominee);
+    validate::admin_config(&admin_config)?;
+
+    AcceptAdminAccountAdmin::build_instruction(program_id, rent_payer, cmd.instance_name)
+}
+
 fn remove_admin_account_instruction(
     client: &RpcClient,
     program_id: &Pubkey,
@@ -149,34 +323,379 @@ fn remove_admin_account_instruction(
 ) -> Result<Instruction> {
     let (instance_pubkey, _) = make_instance_pda(program_id, &cmd.instance_name);
     let instance_account = client.get_account(&instance_pubkey)?;
-    let slq_instance = SlqInstance::try_from_slice(&instance_account.data)?;
+    let slq_instance = SlqInstance::load(&instance_account.data)?;
+    check_rent_payer_can_approve(&slq_instance, rent_payer, ApprovalAction::AdminSetChange)?;
 
     let to_remove_admin_account = Pubkey::from_str(&cmd.account)?;
-    let mut admin_accounts = slq_instance
-        .admin_config
-        .admin_accounts
-        .iter()
-        .filter(|account| **account != Pubkey::default())
-        .copied()
-        .collect::<Vec<Pubkey>>();
 
-    if !admin_accounts.contains(&to_remove_admin_account) {
-        bail!(
+    let mut admin_config = slq_instance.admin_config.clone();
+    let index = match admin_config.position(&to_remove_admin_account) {
+        Some(index) => index,
+        None => bail!(
             "account {} isn't in the admin list",
             &to_remove_admin_account
+        ),
+    };
+    admin_config.admin_accounts.remove(index);
+    validate::admin_config(&admin_config)?;
+
+    RemoveAdminAccountAdmin::build_instruction(
+        program_id,
+        rent_payer,
+        &[*rent_payer],
+        cmd.instance_name,
+        slq_instance.config_epoch,
+        to_remove_admin_account,
+    )
+}
+
+/// `rent_payer` is the admin whose key is rotated.
+fn rotate_key_instruction(
+    client: &RpcClient,
+    program_id: &Pubkey,
+    rent_payer: &Pubkey,
+    signers: &mut Vec<Keypair>,
+    cmd: RotateKeyAdminCommand,
+) -> Result<Instruction> {
+    let (instance_pubkey, _) = make_instance_pda(program_id, &cmd.instance_name);
+    let instance_account = client.get_account(&instance_pubkey)?;
+    let slq_instance = SlqInstance::load(&instance_account.data)?;
+    check_rent_payer_can_approve(&slq_instance, rent_payer, ApprovalAction::AdminSetChange)?;
+
+    let new_admin_keypair = read_keypair_file(&cmd.new_keypair).map_err(|e| anyhow!("{}", e))?;
+    let new_admin_account = new_admin_keypair.pubkey();
+
+    let mut admin_config = slq_instance.admin_config.clone();
+    let index = match admin_config.position(rent_payer) {
+        Some(index) => index,
+        None => bail!("account {} isn't in the admin list", rent_payer),
+    };
+    if admin_config.admin_accounts[index].kind != AdminKind::Key {
+        bail!(
+            "admin {} is an instance, its key can't be rotated",
+            rent_payer
         );
     }
-    if admin_accounts.len() == 1 {
-        bail!("must have at least 1 admin account, add a new admin account before remove the current one");
+    admin_config.admin_accounts[index].pubkey = new_admin_account;
+    validate::admin_config(&admin_config)?;
+
+    let instr = RotateAdminKeyAdmin::build_instruction(
+        program_id,
+        rent_payer,
+        &[*rent_payer],
+        cmd.instance_name,
+        slq_instance.config_epoch,
+        rent_payer,
+        &new_admin_account,
+    )?;
+    signers.push(new_admin_keypair);
+
+    Ok(instr)
+}
+
+fn invoke_instruction(
+    client: &RpcClient,
+    program_id: &Pubkey,
+    rent_payer: &Pubkey,
+    cmd: InvokeAdminCommand,
+) -> Result<Instruction> {
+    let (instance_pubkey, _) = make_instance_pda(program_id, &cmd.instance_name);
+    let instance_account = client.get_account(&instance_pubkey)?;
+    let slq_instance = SlqInstance::load(&instance_account.data)?;
+
+    let tx = load_tx(&cmd.transaction_path)?;
+    let mut instructions = decompile_instructions(tx)?;
+    if instructions.len() != 1 {
+        bail!(
+            "transaction must contain exactly 1 instruction, found {}",
+            instructions.len()
+        );
     }
-    if admin_accounts.len() == usize::from(slq_instance.admin_config.approval_threshold) {
-        bail!("approval threshold is the same as the number of admin accounts, change the approval threshold before remove an account");
+    let instruction = instructions.remove(0);
+
+    let action = invoke_action(
+        program_id,
+        &slq_instance.admin_config,
+        Some(&StoredInstruction::from(&instruction)),
+        ApprovalAction::Default,
+    );
+    check_rent_payer_can_approve(&slq_instance, rent_payer, action)?;
+
+    InvokeAdmin::build_instruction(
+        program_id,
+        rent_payer,
+        &[*rent_payer],
+        cmd.instance_name,
+        slq_instance.config_epoch,
+        &instruction,
+    )
+}
+
+fn set_approval_policy_instruction(
+    client: &RpcClient,
+    program_id: &Pubkey,
+    rent_payer: &Pubkey,
+    cmd: SetApprovalPolicyAdminCommand,
+) -> Result<Instruction> {
+    let (instance_pubkey, _) = make_instance_pda(program_id, &cmd.instance_name);
+    let instance_account = client.get_account(&instance_pubkey)?;
+    let slq_instance = SlqInstance::load(&instance_account.data)?;
+    check_rent_payer_can_approve(&slq_instance, rent_payer, ApprovalAction::ThresholdChange)?;
+
+    let current = slq_instance.admin_config.policy;
+    let policy = ApprovalPolicy {
+        admin_set_threshold: cmd.admin_set.unwrap_or(current.admin_set_threshold),
+        threshold_change_threshold: cmd
+            .threshold_change
+            .unwrap_or(current.threshold_change_threshold),
+        vault_withdrawal_threshold: cmd
+            .vault_withdrawal
+            .unwrap_or(current.vault_withdrawal_threshold),
+        vault_withdrawal_limit: cmd
+            .vault_withdrawal_limit
+            .unwrap_or(current.vault_withdrawal_limit),
+        program_upgrade_threshold: cmd
+            .program_upgrade
+            .unwrap_or(current.program_upgrade_threshold),
+        proposal_threshold: cmd.proposal.unwrap_or(current.proposal_threshold),
+        execution_delay: cmd.execution_delay.unwrap_or(current.execution_delay),
+    };
+
+    if policy == current {
+        bail!("approval policy is unchanged");
     }
 
-    RemoveAdminAccountAdmin::build_instruction(
+    let mut admin_config = slq_instance.admin_config.clone();
+    admin_config.policy = policy;
+    validate::admin_config(&admin_config)?;
+
+    SetApprovalPolicyAdmin::build_instruction(
         program_id,
         rent_payer,
+        &[*rent_payer],
         cmd.instance_name,
-        to_remove_admin_account,
+        slq_instance.config_epoch,
+        policy,
+    )
+}
+
+fn set_admin_config_instruction(
+    client: &RpcClient,
+    program_id: &Pubkey,
+    rent_payer: &Pubkey,
+    cmd: SetAdminConfigAdminCommand,
+) -> Result<Instruction> {
+    let (instance_pubkey, _) = make_instance_pda(program_id, &cmd.instance_name);
+    let instance_account = client.get_account(&instance_pubkey)?;
+    let slq_instance = SlqInstance::load(&instance_account.data)?;
+
+    let current = &slq_instance.admin_config;
+    let action = current.stricter(
+        ApprovalAction::AdminSetChange,
+        ApprovalAction::ThresholdChange,
+    );
+    check_rent_payer_can_approve(&slq_instance, rent_payer, action)?;
+
+    let admin_accounts = cmd
+        .admin_accounts
+        .iter()
+        .map(|account| parse_admin_account(program_id, account))
+        .collect::<Result<Vec<AdminAccount>>>()?;
+
+    let admin_config = AdminConfig {
+        approval_threshold: cmd.approval_threshold,
+        max_admin_accounts: cmd.max_admin_accounts.unwrap_or(current.max_admin_accounts),
+        admin_accounts,
+        policy: current.policy,
+    };
+    validate::admin_config(&admin_config)?;
+
+    SetAdminConfigAdmin::build_instruction(
+        program_id,
+        rent_payer,
+        &[*rent_payer],
+        cmd.instance_name,
+        slq_instance.config_epoch,
+        admin_config,
+    )
+}
+
+/// `rent_payer` is the guardian pausing.
+fn pause_instruction(
+    client: &RpcClient,
+    program_id: &Pubkey,
+    rent_payer: &Pubkey,
+    cmd: PauseAdminCommand,
+) -> Result<Instruction> {
+    let (instance_pubkey, _) = make_instance_pda(program_id, &cmd.instance_name);
+    let instance_account = client.get_account(&instance_pubkey)?;
+    let slq_instance = SlqInstance::load(&instance_account.data)?;
+    if !slq_instance.guardians.contains(rent_payer) {
+        check_admin_role(&slq_instance, rent_payer, Roles::GUARDIAN)?;
+    }
+
+    if slq_instance.paused {
+        bail!("instance is paused already");
+    }
+
+    PauseAdmin::build_instruction(program_id, rent_payer, cmd.instance_name)
+}
+
+fn unpause_instruction(
+    client: &RpcClient,
+    program_id: &Pubkey,
+    rent_payer: &Pubkey,
+    cmd: UnpauseAdminCommand,
+) -> Result<Instruction> {
+    let (instance_pubkey, _) = make_instance_pda(program_id, &cmd.instance_name);
+    let instance_account = client.get_account(&instance_pubkey)?;
+    let slq_instance = SlqInstance::load(&instance_account.data)?;
+    check_rent_payer_weight(&slq_instance, rent_payer, ApprovalAction::Unpause)?;
+
+    if !slq_instance.paused {
+        bail!("instance is not paused");
+    }
+
+    UnpauseAdmin::build_instruction(
+        program_id,
+        rent_payer,
+        &[*rent_payer],
+        cmd.instance_name,
+        slq_instance.config_epoch,
+    )
+}
+
+fn set_recovery_config_instruction(
+    client: &RpcClient,
+    program_id: &Pubkey,
+    rent_payer: &Pubkey,
+    cmd: SetRecoveryConfigAdminCommand,
+) -> Result<Instruction> {
+    let (instance_pubkey, _) = make_instance_pda(program_id, &cmd.instance_name);
+    let instance_account = client.get_account(&instance_pubkey)?;
+    let slq_instance = SlqInstance::load(&instance_account.data)?;
+
+    let admin_config = &slq_instance.admin_config;
+    let action = admin_config.stricter(
+        ApprovalAction::AdminSetChange,
+        ApprovalAction::ThresholdChange,
+    );
+    check_rent_payer_can_approve(&slq_instance, rent_payer, action)?;
+
+    let recovery = if cmd.remove {
+        if slq_instance.recovery.is_none() {
+            bail!("instance has no recovery config");
+        }
+        None
+    } else {
+        let recovery_accounts = cmd
+            .recovery_accounts
+            .iter()
+            .map(|account| Pubkey::from_str(account))
+            .collect::<Result<Vec<Pubkey>, _>>()?;
+        let recovery = RecoveryConfig {
+            recovery_accounts,
+            threshold: cmd.threshold.unwrap_or_default(),
+            inactivity_period: cmd.inactivity_period.unwrap_or_default(),
+            timelock: cmd.timelock.unwrap_or_default(),
+        };
+        validate::recovery_config(&recovery)?;
+        Some(recovery)
+    };
+
+    SetRecoveryConfigAdmin::build_instruction(
+        program_id,
+        rent_payer,
+        &[*rent_payer],
+        cmd.instance_name,
+        slq_instance.config_epoch,
+        recovery,
+    )
+}
+
+fn set_guardians_instruction(
+    client: &RpcClient,
+    program_id: &Pubkey,
+    rent_payer: &Pubkey,
+    cmd: SetGuardiansAdminCommand,
+) -> Result<Instruction> {
+    let (instance_pubkey, _) = make_instance_pda(program_id, &cmd.instance_name);
+    let instance_account = client.get_account(&instance_pubkey)?;
+    let slq_instance = SlqInstance::load(&instance_account.data)?;
+    check_rent_payer_can_approve(&slq_instance, rent_payer, ApprovalAction::AdminSetChange)?;
+
+    let guardians = cmd
+        .guardians
+        .iter()
+        .map(|guardian| Pubkey::from_str(guardian))
+        .collect::<Result<Vec<Pubkey>, _>>()?;
+    validate::guardians(&guardians)?;
+
+    SetGuardiansAdmin::build_instruction(
+        program_id,
+        rent_payer,
+        &[*rent_payer],
+        cmd.instance_name,
+        slq_instance.config_epoch,
+        guardians,
     )
 }
+
+/// Admin commands are approved by `rent_payer` alone, so it must be an admin
+/// whose weight meets the threshold for `action`.
+pub(crate) fn check_rent_payer_can_approve(
+    slq_instance: &SlqInstance,
+    rent_payer: &Pubkey,
+    action: ApprovalAction,
+) -> Result<()> {
+    let execution_delay = slq_instance.admin_config.policy.execution_delay;
+    if execution_delay > 0 {
+        bail!(
+            "instance has an execution delay of {} seconds, submit this as a proposal",
+            execution_delay
+        );
+    }
+
+    check_rent_payer_weight(slq_instance, rent_payer, action)
+}
+
+/// Like `check_rent_payer_can_approve`, for instructions exempt from the execution delay.
+fn check_rent_payer_weight(
+    slq_instance: &SlqInstance,
+    rent_payer: &Pubkey,
+    action: ApprovalAction,
+) -> Result<()> {
+    check_admin_role(slq_instance, rent_payer, Roles::APPROVER)?;
+
+    let weight = slq_instance.admin_config.weight_of(rent_payer).unwrap_or(0);
+    let approval_threshold = slq_instance.admin_config.threshold_for(action);
+    if weight < approval_threshold {
+        bail!(
+            "approval threshold for {:?} is {} and {} has weight {}, use multisig-tx to collect admin signatures",
+            action,
+            approval_threshold,
+            rent_payer,
+            weight
+        );
+    }
+
+    Ok(())
+}
+
+/// Checks that `admin` holds any of `roles`.
+pub(crate) fn check_admin_role(
+    slq_instance: &SlqInstance,
+    admin: &Pubkey,
+    roles: Roles,
+) -> Result<()> {
+    match slq_instance.admin_config.get(admin) {
+        None => bail!("{} is not an admin account of this instance", admin),
+        Some(admin_account) if !admin_account.has_any_role(roles) => bail!(
+            "admin account {} does not hold the {} role, it holds {}",
+            admin,
+            roles,
+            admin_account.roles
+        ),
+        Some(_) => Ok(()),
+    }
+}
@@ -0,0 +1,19 @@
+use anyhow::anyhow;
+use solana_client::client_error::ClientError;
+use solana_sdk::instruction::InstructionError;
+use solana_sdk::transaction::TransactionError;
+
+use slq::error::SlqError;
+
+/// Adds a readable description of any `SlqError` the transaction failed with.
+pub fn decode_client_error(err: ClientError) -> anyhow::Error {
+    if let Some(TransactionError::InstructionError(index, InstructionError::Custom(code))) =
+        err.get_transaction_error()
+    {
+        if let Some(slq_err) = SlqError::from_code(code) {
+            return anyhow!(err).context(format!("instruction {} failed: {}", index, slq_err));
+        }
+    }
+
+    err.into()
+}
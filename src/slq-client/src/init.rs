@@ -14,14 +14,28 @@ use std::str::FromStr;
 use structopt::StructOpt;
 
 use slq::init;
+use slq::state::AdminAccount;
 use slq::state::AdminConfig;
+use slq::state::AdminKind;
+use slq::state::Roles;
 use slq::state::SlqInstance;
+use slq::state::MAX_ADMIN_ACCOUNTS;
 
 #[derive(StructOpt, Debug)]
 pub struct InitializeInstanceCommand {
     instance_name: String,
+    /// The admin weight required to approve an instruction.
     approval_threshold: u8,
+    /// Admin accounts as `PUBKEY[:WEIGHT[:ROLES]]`,
+    /// weight 1 and all roles if omitted.
+    /// `@INSTANCE_NAME` in place of PUBKEY makes another slq instance an admin.
+    ///
+    /// ROLES is a comma-separated list of
+    /// proposer, approver, executor, canceller and guardian.
     admin_accounts: Vec<String>,
+    /// The most admin accounts the instance may have, the ceiling if omitted.
+    #[structopt(long)]
+    max_admin_accounts: Option<u8>,
 }
 
 pub(crate) fn do_command(
@@ -33,14 +47,60 @@ pub(crate) fn do_command(
     let admin_accounts = cmd
         .admin_accounts
         .iter()
-        .map(|account| Pubkey::from_str(account))
-        .collect::<Result<Vec<Pubkey>, _>>()?;
+        .map(|account| parse_admin_account(program_id, account))
+        .collect::<Result<Vec<AdminAccount>>>()?;
 
     init::Init::build_instruction(
         program_id,
         rent_payer,
         cmd.instance_name,
         cmd.approval_threshold,
+        cmd.max_admin_accounts.unwrap_or(MAX_ADMIN_ACCOUNTS as u8),
         admin_accounts,
     )
 }
+
+pub(crate) fn parse_admin_account(program_id: &Pubkey, account: &str) -> Result<AdminAccount> {
+    let mut parts = account.splitn(3, ':');
+    let pubkey = parts.next().unwrap_or_default();
+    let weight = match parts.next() {
+        Some(weight) => weight
+            .parse()
+            .with_context(|| format!("invalid weight for admin account {}", pubkey))?,
+        None => 1,
+    };
+    let roles = match parts.next() {
+        Some(roles) => Roles::from_str(roles)?,
+        None => Roles::ALL,
+    };
+
+    let (pubkey, kind) = parse_admin_pubkey(program_id, pubkey)?;
+
+    Ok(AdminAccount {
+        pubkey,
+        weight,
+        roles,
+        kind,
+    })
+}
+
+/// Parses `PUBKEY`, or `@INSTANCE_NAME` for an slq instance
+/// approving through its authority pda.
+pub(crate) fn parse_admin_pubkey(
+    program_id: &Pubkey,
+    account: &str,
+) -> Result<(Pubkey, AdminKind)> {
+    match account.strip_prefix('@') {
+        Some(instance_name) => {
+            let (instance_pda, _) = init::make_instance_pda(program_id, instance_name);
+            let (_, authority_pda_bump_seed) = init::make_authority_pda(program_id, &instance_pda);
+            Ok((
+                instance_pda,
+                AdminKind::Instance {
+                    authority_pda_bump_seed,
+                },
+            ))
+        }
+        None => Ok((Pubkey::from_str(account)?, AdminKind::Key)),
+    }
+}
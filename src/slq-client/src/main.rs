@@ -4,8 +4,9 @@ use anyhow::{anyhow, bail, Context, Result};
 use borsh::de::BorshDeserialize;
 use init::InitializeInstanceCommand;
 use log::info;
-use slq::init::make_instance_pda;
-use slq::state::SlqInstance;
+use slq::init::{make_authority_pda, make_instance_pda};
+use slq::migrate::MigrateAccount;
+use slq::state::{AccountType, SlqAccount, SlqInstance};
 use solana_client::rpc_client::RpcClient;
 use solana_sdk::commitment_config::CommitmentConfig;
 use solana_sdk::instruction::Instruction;
@@ -20,12 +21,17 @@ use structopt::StructOpt;
 use admin::AdminCommand;
 use multisig::MultisigCommand;
 use multisig_tx::MultisigTxCommand;
+use proposal::ProposalCommand;
+use recovery::RecoveryCommand;
 use vault::VaultCommand;
 
 mod admin;
+mod error;
 mod init;
 mod multisig;
 mod multisig_tx;
+mod proposal;
+mod recovery;
 mod vault;
 
 fn main() -> Result<()> {
@@ -39,17 +45,43 @@ fn main() -> Result<()> {
 
     let opt = Opt::from_args();
 
+    // Signers besides the config keypair, for commands that need them.
+    let mut signers = Vec::new();
+
     let instr = match opt.cmd {
         Command::GetInstanceState { instance_name } => {
             // todo: add program_id to config
             let (instance_pubkey, _) = make_instance_pda(&program_keypair.pubkey(), &instance_name);
             let instance_account = client.get_account(&instance_pubkey)?;
-            let instance_account_data = SlqInstance::try_from_slice(&instance_account.data)?;
+            let instance_account_data = SlqInstance::load(&instance_account.data)?;
+            let (authority_pubkey, _) =
+                make_authority_pda(&program_keypair.pubkey(), &instance_pubkey);
 
             println!("{:#?}", instance_account_data);
+            println!(
+                "total admin weight: {}",
+                instance_account_data.admin_config.total_weight()
+            );
+            println!("authority: {}", authority_pubkey);
 
             return Ok(());
         }
+        Command::ListStaleProposals { instance_name } => {
+            proposal::list_stale_proposals(&client, &program_keypair.pubkey(), &instance_name)?;
+
+            return Ok(());
+        }
+        Command::MigrateInstance { instance_name } => {
+            let (instance_pubkey, _) = make_instance_pda(&program_keypair.pubkey(), &instance_name);
+
+            MigrateAccount::build_instruction(
+                &program_keypair.pubkey(),
+                &config.keypair.pubkey(),
+                &instance_pubkey,
+                AccountType::Instance,
+                &instance_name,
+            )?
+        }
         Command::InitializeInstance(cmd) => init::do_command(
             &client,
             &program_keypair.pubkey(),
@@ -60,6 +92,7 @@ fn main() -> Result<()> {
             &client,
             &program_keypair.pubkey(),
             &config.keypair.pubkey(),
+            &mut signers,
             cmd,
         )?,
         Command::Multisig(cmd) => {
@@ -76,17 +109,34 @@ fn main() -> Result<()> {
             &config.keypair.pubkey(),
             cmd,
         )?,
+        Command::Proposal(cmd) => proposal::do_command(
+            &client,
+            &program_keypair.pubkey(),
+            &config.keypair.pubkey(),
+            cmd,
+        )?,
+        Command::Recovery(cmd) => recovery::do_command(
+            &client,
+            &program_keypair.pubkey(),
+            &config.keypair.pubkey(),
+            cmd,
+        )?,
     };
 
     let blockhash = client.get_latest_blockhash()?;
+    let signers: Vec<&Keypair> = std::iter::once(&config.keypair)
+        .chain(signers.iter())
+        .collect();
     let tx = Transaction::new_signed_with_payer(
         &[instr],
         Some(&config.keypair.pubkey()),
-        &[&config.keypair],
+        &signers,
         blockhash,
     );
 
-    let sig = client.send_and_confirm_transaction_with_spinner(&tx)?;
+    let sig = client
+        .send_and_confirm_transaction_with_spinner(&tx)
+        .map_err(error::decode_client_error)?;
     info!("sig: {}", sig);
 
     Ok(())
@@ -101,11 +151,23 @@ struct Opt {
 #[derive(StructOpt, Debug)]
 enum Command {
     InitializeInstance(InitializeInstanceCommand),
-    GetInstanceState { instance_name: String },
+    GetInstanceState {
+        instance_name: String,
+    },
+    /// List pending proposals that can no longer be approved or executed.
+    ListStaleProposals {
+        instance_name: String,
+    },
+    /// Upgrade the instance account to the current layout.
+    MigrateInstance {
+        instance_name: String,
+    },
     Admin(AdminCommand),
     Multisig(MultisigCommand),
     MultisigTx(MultisigTxCommand),
     Vault(VaultCommand),
+    Proposal(ProposalCommand),
+    Recovery(RecoveryCommand),
 }
 
 pub struct Config {
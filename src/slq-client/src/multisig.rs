@@ -23,8 +23,8 @@ use std::str::FromStr;
 use structopt::StructOpt;
 
 use borsh::BorshDeserialize;
-use slq::state::{AdminConfig, SlqInstance, MAX_ADMIN_ACCOUNTS};
-use solana_sdk::borsh::get_instance_packed_len;
+use slq::state::{AdminConfig, SlqInstance};
+use solana_sdk::borsh1::get_instance_packed_len;
 
 use serde::{Deserialize, Serialize};
 use std::fs;
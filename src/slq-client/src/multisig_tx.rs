@@ -5,15 +5,13 @@ use log::info;
 use solana_client::rpc_client::RpcClient;
 use solana_sdk::commitment_config::CommitmentConfig;
 use solana_sdk::hash::Hash;
-use solana_sdk::instruction::Instruction;
+use solana_sdk::instruction::{AccountMeta, Instruction};
 use solana_sdk::message::Message;
-use solana_sdk::message::SanitizedMessage;
 use solana_sdk::nonce::State;
 use solana_sdk::pubkey::Pubkey;
 use solana_sdk::signature::{read_keypair_file, Keypair, Signer};
 use solana_sdk::signers::Signers;
 use solana_sdk::system_instruction;
-use solana_sdk::sysvar::instructions::construct_instructions_data;
 use solana_sdk::transaction::Transaction;
 use std::convert::TryFrom;
 use std::convert::TryInto;
@@ -23,9 +21,12 @@ use std::str::FromStr;
 use structopt::StructOpt;
 
 use borsh::BorshDeserialize;
-use slq::state::{AdminConfig, SlqInstance, MAX_ADMIN_ACCOUNTS};
-use solana_sdk::borsh::get_instance_packed_len;
+use slq::init::{make_authority_pda, make_instance_pda};
+use slq::state::{AdminConfig, AdminKind, ApprovalAction, Roles, SlqAccount, SlqInstance};
+use solana_sdk::borsh1::get_instance_packed_len;
 
+use crate::admin::check_admin_role;
+use crate::error::decode_client_error;
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::fs::File;
@@ -88,6 +89,13 @@ pub struct CancelTransaction {
 #[derive(StructOpt, Debug)]
 pub struct StartDestroy {
     instance_name: String,
+    /// Receives the instance's rent lamports.
+    recipient: String,
+    transaction_path: PathBuf,
+    /// Admins that will sign the transaction, all that sign with their own key if omitted.
+    /// Instance admins can't sign it, as they approve through their authority.
+    #[structopt(long = "admin")]
+    admins: Vec<String>,
 }
 
 #[derive(StructOpt, Debug)]
@@ -105,8 +113,9 @@ pub(crate) fn do_command(
         MultisigTxCommand::StartTransaction(cmd) => cmd.exec(client, program_id, payer),
         MultisigTxCommand::SignTransaction(cmd) => cmd.exec(program_id, payer),
         MultisigTxCommand::ExecTransaction(cmd) => cmd.exec(client, program_id, payer),
+        MultisigTxCommand::CancelTransaction(cmd) => cmd.exec(client, payer),
+        MultisigTxCommand::StartDestroy(cmd) => cmd.exec(client, program_id, payer),
         MultisigTxCommand::DemoTransaction(cmd) => cmd.exec(client, program_id, &payer.pubkey()),
-        _ => todo!(),
     }
 }
 
@@ -117,22 +126,12 @@ impl StartTransaction {
         let rent_payer_pubkey = rent_payer.pubkey();
 
         // for testing
-        fs::write(&PathBuf::from("nonce_keypair"), nonce_account.to_bytes())?;
+        fs::write(PathBuf::from("nonce_keypair"), nonce_account.to_bytes())?;
         println!("nonce_keypair saved, pubkey: {:#?}", nonce_account_pubkey);
 
         // load and decompile offchain tx file
         let tx = load_tx(&self.transaction_path)?;
-
-        let mut instr_offchain = vec![];
-
-        let instr_num = tx.message.instructions.len();
-        let message = SanitizedMessage::try_from(tx.message)?;
-        let message = construct_instructions_data(&message);
-
-        for i in 0..instr_num {
-            let decompiled_instr = Message::deserialize_instruction(i, &message)?;
-            instr_offchain.push(decompiled_instr);
-        }
+        let mut instr_offchain = decompile_instructions(tx)?;
 
         let instr_withdraw_nonce = slq::nonce::WithdrawNonceAccount::build_instruction(
             program_id,
@@ -156,7 +155,9 @@ impl StartTransaction {
         let signers: Vec<&dyn Signer> = vec![&nonce_account, rent_payer];
         tx_onchain.try_sign(&signers, client.get_latest_blockhash()?)?;
 
-        client.send_and_confirm_transaction(&tx_onchain)?;
+        client
+            .send_and_confirm_transaction(&tx_onchain)
+            .map_err(decode_client_error)?;
 
         /*
                 // change nonce authority to slq onchain program
@@ -193,7 +194,7 @@ impl StartTransaction {
 
         let onchain_nonce_account = client.get_account(&nonce_account_pubkey)?;
         let nonce_data = solana_client::nonce_utils::data_from_account(&onchain_nonce_account)?;
-        let nonce_hash = nonce_data.blockhash;
+        let nonce_hash = nonce_data.blockhash();
 
         let signers: Vec<&dyn Signer> = vec![rent_payer];
         tx_offchain.try_partial_sign(&signers, nonce_hash);
@@ -217,7 +218,7 @@ impl SignTransaction {
         let mut tx = load_tx(&self.transaction_path)?;
 
         // for test
-        let signer = fs::read(&PathBuf::from("another_signer"))?;
+        let signer = fs::read(PathBuf::from("another_signer"))?;
         let signer = Keypair::from_bytes(&signer)?;
         println!("another_signer_pubkey: {:#?}", signer.pubkey());
 
@@ -244,9 +245,11 @@ impl ExecTransaction {
         println!("exec tx: {:#?}", tx);
         // todo: exec need to do multisig verify and pda verify
 
-        client.send_and_confirm_transaction(&tx)?;
+        client
+            .send_and_confirm_transaction(&tx)
+            .map_err(decode_client_error)?;
 
-        let nonce = fs::read(&PathBuf::from("nonce_keypair"))?;
+        let nonce = fs::read(PathBuf::from("nonce_keypair"))?;
         let nonce = Keypair::from_bytes(&nonce)?;
 
         // withdraw nonce
@@ -266,7 +269,9 @@ impl ExecTransaction {
         let signers: Vec<&dyn Signer> = vec![rent_payer];
         tx.try_sign(&signers, client.get_latest_blockhash()?)?;
 
-        let sig = client.send_and_confirm_transaction(&tx)?;
+        let sig = client
+            .send_and_confirm_transaction(&tx)
+            .map_err(decode_client_error)?;
         println!("withdraw sig: {:#?}", sig);
 
         // should panic
@@ -280,19 +285,165 @@ impl ExecTransaction {
     }
 }
 
+impl CancelTransaction {
+    fn exec(&self, client: &RpcClient, rent_payer: &Keypair) -> Result<()> {
+        let tx = load_tx(&self.transaction_path)?;
+
+        // A durable nonce transaction starts by advancing its nonce account.
+        let nonce_pubkey = tx
+            .message
+            .instructions
+            .first()
+            .and_then(|instr| instr.accounts.first())
+            .and_then(|&index| tx.message.account_keys.get(usize::from(index)))
+            .ok_or_else(|| anyhow!("transaction does not use a nonce account"))?;
+
+        let nonce_lamports = client.get_balance(nonce_pubkey)?;
+        let instr_withdraw_nonce = system_instruction::withdraw_nonce_account(
+            nonce_pubkey,
+            &rent_payer.pubkey(),
+            &rent_payer.pubkey(),
+            nonce_lamports,
+        );
+
+        let mut tx =
+            Transaction::new_with_payer(&[instr_withdraw_nonce], Some(&rent_payer.pubkey()));
+
+        let signers: Vec<&dyn Signer> = vec![rent_payer];
+        tx.try_sign(&signers, client.get_latest_blockhash()?)?;
+
+        let sig = client
+            .send_and_confirm_transaction(&tx)
+            .map_err(decode_client_error)?;
+        println!("withdraw sig: {:#?}", sig);
+
+        Ok(())
+    }
+}
+
+impl StartDestroy {
+    fn exec(&self, client: &RpcClient, program_id: &Pubkey, rent_payer: &Keypair) -> Result<()> {
+        let (instance_pubkey, _) = make_instance_pda(program_id, &self.instance_name);
+        let instance_account = client.get_account(&instance_pubkey)?;
+        let slq_instance = SlqInstance::load(&instance_account.data)?;
+
+        if slq_instance.open_proposals > 0 {
+            bail!(
+                "instance has {} open proposals, execute them before destroying it",
+                slq_instance.open_proposals
+            );
+        }
+
+        if slq_instance.vaults > 0 {
+            bail!(
+                "instance has {} vaults, close them before destroying it",
+                slq_instance.vaults
+            );
+        }
+
+        let (authority_pubkey, _) = make_authority_pda(program_id, &instance_pubkey);
+        let authority_lamports = client.get_balance(&authority_pubkey)?;
+        if authority_lamports > 0 {
+            bail!(
+                "authority {} holds {} lamports, withdraw them before destroying the instance",
+                authority_pubkey,
+                authority_lamports
+            );
+        }
+
+        let admin_config = &slq_instance.admin_config;
+
+        let admins = if self.admins.is_empty() {
+            admin_config
+                .admin_accounts
+                .iter()
+                .filter(|admin_account| {
+                    admin_account.kind == AdminKind::Key && admin_account.has_role(Roles::APPROVER)
+                })
+                .map(|admin_account| admin_account.pubkey)
+                .collect()
+        } else {
+            let admins = self
+                .admins
+                .iter()
+                .map(|admin| Pubkey::from_str(admin))
+                .collect::<Result<Vec<Pubkey>, _>>()?;
+            for admin in &admins {
+                check_admin_role(&slq_instance, admin, Roles::APPROVER)?;
+                let is_instance = admin_config.admin_accounts.iter().any(|admin_account| {
+                    admin_account.pubkey == *admin && admin_account.kind != AdminKind::Key
+                });
+                if is_instance {
+                    bail!(
+                        "admin {} is an instance, it approves with its authority through a proposal",
+                        admin
+                    );
+                }
+            }
+            admins
+        };
+
+        let signing_weight: u32 = admins
+            .iter()
+            .filter_map(|admin| admin_config.weight_of(admin))
+            .map(u32::from)
+            .sum();
+        if admin_config.policy.execution_delay > 0 {
+            bail!(
+                "instance has an execution delay of {} seconds, destroy it through a proposal",
+                admin_config.policy.execution_delay
+            );
+        }
+
+        let approval_threshold = admin_config.threshold_for(ApprovalAction::Default);
+        if signing_weight < u32::from(approval_threshold) {
+            bail!(
+                "signing admins have weight {}, approval threshold is {}",
+                signing_weight,
+                approval_threshold
+            );
+        }
+
+        let recipient = Pubkey::from_str(&self.recipient)?;
+        let instr = slq::admin::DestroyInstanceAdmin::build_instruction(
+            program_id,
+            &rent_payer.pubkey(),
+            &admins,
+            self.instance_name.clone(),
+            slq_instance.config_epoch,
+            &recipient,
+        )?;
+
+        let tx = Transaction::new_with_payer(&[instr], Some(&rent_payer.pubkey()));
+        write_tx_to_file(&self.transaction_path, &tx)?;
+
+        StartTransaction {
+            instance_name: self.instance_name.clone(),
+            transaction_name: "destroy".to_string(),
+            transaction_path: self.transaction_path.clone(),
+        }
+        .exec(client, program_id, rent_payer)
+    }
+}
+
 impl DemoTransaction {
     fn exec(&self, client: &RpcClient, program_id: &Pubkey, rent_payer: &Pubkey) -> Result<()> {
-        let another_signer = fs::read(&PathBuf::from("another_signer"))?;
+        let another_signer = fs::read(PathBuf::from("another_signer"))?;
         let another_signer = Keypair::from_bytes(&another_signer)?;
 
-        let instr =
-            slq::admin::ChangeApprovalThresholdAdmin::build_instruction_with_admin_accounts(
-                program_id,
-                rent_payer,
-                &[another_signer.pubkey()],
-                "foo".to_string(),
-                1,
-            )?;
+        let instance_name = "foo".to_string();
+        let (instance_pubkey, _) = make_instance_pda(program_id, &instance_name);
+        let instance_account = client.get_account(&instance_pubkey)?;
+        let slq_instance = SlqInstance::load(&instance_account.data)?;
+
+        let instr = slq::admin::ChangeApprovalThresholdAdmin::build_instruction(
+            program_id,
+            rent_payer,
+            &[another_signer.pubkey()],
+            instance_name,
+            slq_instance.config_epoch,
+            1,
+        )?;
 
         let tx = Transaction::new_with_payer(&[instr], Some(rent_payer));
         println!("demo tx: {:#?}", tx);
@@ -304,16 +455,51 @@ impl DemoTransaction {
 }
 
 fn write_tx_to_file(path: &Path, tx: &Transaction) -> Result<()> {
-    let file = File::create(&path)?;
+    let file = File::create(path)?;
     let mut writer = BufWriter::new(file);
 
     serde_json::to_writer(&mut writer, tx).map_err(|e| anyhow!("{}", e))
 }
 
-fn load_tx(path: &Path) -> Result<Transaction> {
+pub(crate) fn load_tx(path: &Path) -> Result<Transaction> {
     let file = File::open(path)?;
     let reader = BufReader::new(file);
     let tx = serde_json::from_reader(reader)?;
 
     Ok(tx)
 }
+
+pub(crate) fn decompile_instructions(tx: Transaction) -> Result<Vec<Instruction>> {
+    let message = tx.message;
+
+    let mut instrs = vec![];
+    for compiled in &message.instructions {
+        let program_id = message
+            .account_keys
+            .get(usize::from(compiled.program_id_index))
+            .ok_or_else(|| anyhow!("program id index out of bounds"))?;
+        let accounts = compiled
+            .accounts
+            .iter()
+            .map(|&i| {
+                let i = usize::from(i);
+                let pubkey = message
+                    .account_keys
+                    .get(i)
+                    .ok_or_else(|| anyhow!("account index out of bounds"))?;
+                Ok(AccountMeta {
+                    pubkey: *pubkey,
+                    is_signer: message.is_signer(i),
+                    is_writable: message.is_writable(i),
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        instrs.push(Instruction {
+            program_id: *program_id,
+            accounts,
+            data: compiled.data.clone(),
+        });
+    }
+
+    Ok(instrs)
+}
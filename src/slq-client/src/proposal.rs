@@ -0,0 +1,316 @@
+#![allow(unused)]
+
+use anyhow::{anyhow, bail, Context, Result};
+use log::info;
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::pubkey::Pubkey;
+use std::path::PathBuf;
+use structopt::StructOpt;
+
+use borsh::BorshDeserialize;
+use slq::admin::invoke_action;
+use slq::init::make_instance_pda;
+use slq::proposal::{make_proposal_pda, Approve, Cancel, CreateProposal, Execute};
+use slq::state::{
+    read_header, AccountType, ApprovalAction, Expiry, Proposal, Roles, SlqAccount, SlqInstance,
+    ACCOUNT_HEADER_LEN,
+};
+use solana_client::rpc_config::RpcProgramAccountsConfig;
+use solana_client::rpc_filter::{Memcmp, MemcmpEncodedBytes, RpcFilterType};
+use solana_sdk::account::from_account;
+use solana_sdk::clock::Clock;
+use solana_sdk::sysvar;
+use std::str::FromStr;
+
+use crate::admin::check_admin_role;
+use crate::multisig_tx::{decompile_instructions, load_tx};
+
+#[derive(StructOpt, Debug)]
+pub enum ProposalCommand {
+    /// Store the instructions of a transaction file in an on-chain proposal.
+    Create(CreateProposalCommand),
+    /// Approve a proposal with the configured keypair.
+    Approve(ApproveProposalCommand),
+    /// Invoke a proposal's instructions once it has enough approvals.
+    Execute(ExecuteProposalCommand),
+    /// Cancel a pending proposal, sending its rent to the recipient.
+    Cancel(CancelProposalCommand),
+}
+
+#[derive(StructOpt, Debug)]
+pub struct CreateProposalCommand {
+    instance_name: String,
+    proposal_name: String,
+    transaction_path: PathBuf,
+    /// The last slot the proposal can be approved or executed in.
+    #[structopt(long, conflicts_with = "expiry-timestamp")]
+    expiry_slot: Option<u64>,
+    /// The last unix timestamp the proposal can be approved or executed at.
+    #[structopt(long)]
+    expiry_timestamp: Option<i64>,
+}
+
+#[derive(StructOpt, Debug)]
+pub struct ApproveProposalCommand {
+    instance_name: String,
+    proposal_name: String,
+}
+
+#[derive(StructOpt, Debug)]
+pub struct ExecuteProposalCommand {
+    instance_name: String,
+    proposal_name: String,
+}
+
+#[derive(StructOpt, Debug)]
+pub struct CancelProposalCommand {
+    instance_name: String,
+    proposal_name: String,
+    recipient: String,
+}
+
+pub(crate) fn do_command(
+    client: &RpcClient,
+    program_id: &Pubkey,
+    rent_payer: &Pubkey,
+    cmd: ProposalCommand,
+) -> Result<Instruction> {
+    match cmd {
+        ProposalCommand::Create(cmd) => {
+            create_proposal_instruction(client, program_id, rent_payer, cmd)
+        }
+        ProposalCommand::Approve(cmd) => {
+            approve_proposal_instruction(client, program_id, rent_payer, cmd)
+        }
+        ProposalCommand::Execute(cmd) => {
+            execute_proposal_instruction(client, program_id, rent_payer, cmd)
+        }
+        ProposalCommand::Cancel(cmd) => {
+            cancel_proposal_instruction(client, program_id, rent_payer, cmd)
+        }
+    }
+}
+
+fn create_proposal_instruction(
+    client: &RpcClient,
+    program_id: &Pubkey,
+    rent_payer: &Pubkey,
+    cmd: CreateProposalCommand,
+) -> Result<Instruction> {
+    let tx = load_tx(&cmd.transaction_path)?;
+    let instructions = decompile_instructions(tx)?;
+
+    let expiry = match (cmd.expiry_slot, cmd.expiry_timestamp) {
+        (Some(slot), _) => Some(Expiry::Slot(slot)),
+        (None, Some(timestamp)) => Some(Expiry::UnixTimestamp(timestamp)),
+        (None, None) => None,
+    };
+    if let Some(expiry) = expiry {
+        if expiry.has_passed(&get_clock(client)?) {
+            bail!("expiry {:?} has already passed", expiry);
+        }
+    }
+
+    CreateProposal::build_instruction(
+        program_id,
+        rent_payer,
+        rent_payer,
+        cmd.instance_name,
+        cmd.proposal_name,
+        &instructions,
+        expiry,
+    )
+}
+
+fn approve_proposal_instruction(
+    client: &RpcClient,
+    program_id: &Pubkey,
+    admin: &Pubkey,
+    cmd: ApproveProposalCommand,
+) -> Result<Instruction> {
+    let (instance_pubkey, _) = make_instance_pda(program_id, &cmd.instance_name);
+    let instance_account = client.get_account(&instance_pubkey)?;
+    let slq_instance = SlqInstance::load(&instance_account.data)?;
+    check_admin_role(&slq_instance, admin, Roles::APPROVER)?;
+    let proposal = get_proposal(client, program_id, &instance_pubkey, &cmd.proposal_name)?;
+
+    if proposal.executed {
+        bail!("proposal {} has already been executed", cmd.proposal_name);
+    }
+    check_proposal_is_current(&slq_instance, &proposal, &get_clock(client)?)?;
+
+    Approve::build_instruction(program_id, admin, cmd.instance_name, cmd.proposal_name)
+}
+
+fn execute_proposal_instruction(
+    client: &RpcClient,
+    program_id: &Pubkey,
+    executor: &Pubkey,
+    cmd: ExecuteProposalCommand,
+) -> Result<Instruction> {
+    let (instance_pubkey, _) = make_instance_pda(program_id, &cmd.instance_name);
+    let instance_account = client.get_account(&instance_pubkey)?;
+    let slq_instance = SlqInstance::load(&instance_account.data)?;
+    check_admin_role(&slq_instance, executor, Roles::EXECUTOR)?;
+    let proposal = get_proposal(client, program_id, &instance_pubkey, &cmd.proposal_name)?;
+
+    if proposal.executed {
+        bail!("proposal {} has already been executed", cmd.proposal_name);
+    }
+    check_proposal_is_current(&slq_instance, &proposal, &get_clock(client)?)?;
+
+    let approved_weight = slq_instance
+        .admin_config
+        .approved_weight(proposal.approvals);
+    let action = invoke_action(
+        program_id,
+        &slq_instance.admin_config,
+        &proposal.instructions,
+        ApprovalAction::Proposal,
+    );
+    let approval_threshold = slq_instance.admin_config.threshold_for(action);
+    if approved_weight < u32::from(approval_threshold) {
+        bail!(
+            "proposal has approved weight {}, approval threshold is {}",
+            approved_weight,
+            approval_threshold
+        );
+    }
+
+    Execute::build_instruction(
+        program_id,
+        executor,
+        cmd.instance_name,
+        cmd.proposal_name,
+        &proposal,
+    )
+}
+
+fn cancel_proposal_instruction(
+    client: &RpcClient,
+    program_id: &Pubkey,
+    canceller: &Pubkey,
+    cmd: CancelProposalCommand,
+) -> Result<Instruction> {
+    let (instance_pubkey, _) = make_instance_pda(program_id, &cmd.instance_name);
+    let instance_account = client.get_account(&instance_pubkey)?;
+    let slq_instance = SlqInstance::load(&instance_account.data)?;
+    if !slq_instance.guardians.contains(canceller) {
+        check_admin_role(
+            &slq_instance,
+            canceller,
+            Roles::CANCELLER.union(Roles::GUARDIAN),
+        )?;
+    }
+    let proposal = get_proposal(client, program_id, &instance_pubkey, &cmd.proposal_name)?;
+
+    if proposal.executed {
+        bail!("proposal {} has already been executed", cmd.proposal_name);
+    }
+
+    let recipient = Pubkey::from_str(&cmd.recipient)?;
+
+    Cancel::build_instruction(
+        program_id,
+        canceller,
+        cmd.instance_name,
+        cmd.proposal_name,
+        &recipient,
+    )
+}
+
+fn get_proposal(
+    client: &RpcClient,
+    program_id: &Pubkey,
+    instance_pubkey: &Pubkey,
+    proposal_name: &str,
+) -> Result<Proposal> {
+    let (proposal_pubkey, _) = make_proposal_pda(program_id, instance_pubkey, proposal_name);
+    let proposal_account = client
+        .get_account(&proposal_pubkey)
+        .with_context(|| format!("unable to get proposal {}", proposal_name))?;
+
+    Ok(Proposal::load(&proposal_account.data)?)
+}
+
+pub(crate) fn list_stale_proposals(
+    client: &RpcClient,
+    program_id: &Pubkey,
+    instance_name: &str,
+) -> Result<()> {
+    let (instance_pubkey, _) = make_instance_pda(program_id, instance_name);
+    let instance_account = client.get_account(&instance_pubkey)?;
+    let slq_instance = SlqInstance::load(&instance_account.data)?;
+    let clock = get_clock(client)?;
+
+    // Proposals start with the instance they belong to.
+    let config = RpcProgramAccountsConfig {
+        filters: Some(vec![RpcFilterType::Memcmp(Memcmp::new(
+            ACCOUNT_HEADER_LEN,
+            MemcmpEncodedBytes::Base58(instance_pubkey.to_string()),
+        ))]),
+        ..RpcProgramAccountsConfig::default()
+    };
+    let accounts = client.get_program_accounts_with_config(program_id, config)?;
+
+    for (proposal_pubkey, account) in accounts {
+        match read_header(&account.data) {
+            Ok(header) if header.account_type == AccountType::Proposal => {}
+            _ => continue,
+        }
+
+        let proposal = match Proposal::load(&account.data) {
+            Ok(proposal) => proposal,
+            Err(err) => {
+                println!(
+                    "{}: unreadable, migrate it first ({})",
+                    proposal_pubkey, err
+                );
+                continue;
+            }
+        };
+        if proposal.executed {
+            continue;
+        }
+
+        if proposal.config_epoch != slq_instance.config_epoch {
+            println!(
+                "{}: stale, created in config epoch {}, the instance is in epoch {}",
+                proposal_pubkey, proposal.config_epoch, slq_instance.config_epoch
+            );
+        } else if proposal.has_expired(&clock) {
+            println!(
+                "{}: expired at {:?}",
+                proposal_pubkey,
+                proposal.expiry.unwrap()
+            );
+        }
+    }
+
+    Ok(())
+}
+
+fn check_proposal_is_current(
+    slq_instance: &SlqInstance,
+    proposal: &Proposal,
+    clock: &Clock,
+) -> Result<()> {
+    if proposal.config_epoch != slq_instance.config_epoch {
+        bail!(
+            "proposal was created in config epoch {}, the instance is in epoch {}, cancel it and create a new one",
+            proposal.config_epoch,
+            slq_instance.config_epoch
+        );
+    }
+    if proposal.has_expired(clock) {
+        bail!("proposal expired at {:?}", proposal.expiry.unwrap());
+    }
+
+    Ok(())
+}
+
+pub(crate) fn get_clock(client: &RpcClient) -> Result<Clock> {
+    let clock_account = client.get_account(&sysvar::clock::id())?;
+    from_account(&clock_account).ok_or_else(|| anyhow!("unable to read the clock sysvar"))
+}
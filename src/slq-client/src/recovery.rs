@@ -0,0 +1,183 @@
+#![allow(unused)]
+
+use anyhow::{anyhow, bail, Context, Result};
+use log::info;
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::pubkey::Pubkey;
+use structopt::StructOpt;
+
+use slq::init::make_instance_pda;
+use slq::recovery::{CompleteRecovery, StartRecovery, VetoRecovery};
+use slq::state::{AdminAccount, AdminConfig, SlqAccount, SlqInstance};
+use slq::validate;
+
+use crate::init::parse_admin_account;
+use crate::proposal::get_clock;
+
+#[derive(StructOpt, Debug)]
+pub enum RecoveryCommand {
+    /// Start replacing the admins of an inactive instance, as a recovery account.
+    Start(StartRecoveryCommand),
+    /// Drop a pending recovery, as any admin.
+    Veto(VetoRecoveryCommand),
+    /// Replace the admins once a pending recovery's timelock has elapsed.
+    Complete(CompleteRecoveryCommand),
+}
+
+/// The approval policy is kept as is.
+#[derive(StructOpt, Debug)]
+pub struct StartRecoveryCommand {
+    instance_name: String,
+    /// The admin weight required to approve an instruction.
+    approval_threshold: u8,
+    /// Admin accounts as `PUBKEY[:WEIGHT[:ROLES]]`,
+    /// weight 1 and all roles if omitted.
+    /// `@INSTANCE_NAME` in place of PUBKEY makes another slq instance an admin.
+    admin_accounts: Vec<String>,
+    /// The most admin accounts the instance may have, the current maximum if omitted.
+    #[structopt(long)]
+    max_admin_accounts: Option<u8>,
+}
+
+#[derive(StructOpt, Debug)]
+pub struct VetoRecoveryCommand {
+    instance_name: String,
+}
+
+#[derive(StructOpt, Debug)]
+pub struct CompleteRecoveryCommand {
+    instance_name: String,
+}
+
+pub(crate) fn do_command(
+    client: &RpcClient,
+    program_id: &Pubkey,
+    rent_payer: &Pubkey,
+    cmd: RecoveryCommand,
+) -> Result<Instruction> {
+    match cmd {
+        RecoveryCommand::Start(cmd) => {
+            start_recovery_instruction(client, program_id, rent_payer, cmd)
+        }
+        RecoveryCommand::Veto(cmd) => {
+            veto_recovery_instruction(client, program_id, rent_payer, cmd)
+        }
+        RecoveryCommand::Complete(cmd) => {
+            complete_recovery_instruction(client, program_id, rent_payer, cmd)
+        }
+    }
+}
+
+/// `rent_payer` is the only recovery account signing.
+fn start_recovery_instruction(
+    client: &RpcClient,
+    program_id: &Pubkey,
+    rent_payer: &Pubkey,
+    cmd: StartRecoveryCommand,
+) -> Result<Instruction> {
+    let slq_instance = get_instance(client, program_id, &cmd.instance_name)?;
+
+    let recovery = match &slq_instance.recovery {
+        Some(recovery) => recovery,
+        None => bail!("instance has no recovery config"),
+    };
+    if !recovery.recovery_accounts.contains(rent_payer) {
+        bail!("{} is not a recovery account of this instance", rent_payer);
+    }
+    if recovery.threshold > 1 {
+        bail!(
+            "recovery threshold is {}, use multisig-tx to collect recovery signatures",
+            recovery.threshold
+        );
+    }
+    if slq_instance.pending_recovery.is_some() {
+        bail!("a recovery is already pending");
+    }
+
+    let now = get_clock(client)?.unix_timestamp;
+    let inactive_from = slq_instance
+        .last_admin_action
+        .saturating_add(recovery.inactivity_period);
+    if now < inactive_from {
+        bail!("admins are inactive from {}, it is {}", inactive_from, now);
+    }
+
+    let admin_accounts = cmd
+        .admin_accounts
+        .iter()
+        .map(|account| parse_admin_account(program_id, account))
+        .collect::<Result<Vec<AdminAccount>>>()?;
+
+    let current = &slq_instance.admin_config;
+    let admin_config = AdminConfig {
+        approval_threshold: cmd.approval_threshold,
+        max_admin_accounts: cmd.max_admin_accounts.unwrap_or(current.max_admin_accounts),
+        admin_accounts,
+        policy: current.policy,
+    };
+    validate::admin_config(&admin_config)?;
+
+    StartRecovery::build_instruction(
+        program_id,
+        rent_payer,
+        &[*rent_payer],
+        cmd.instance_name,
+        admin_config,
+    )
+}
+
+/// `rent_payer` is the admin vetoing.
+fn veto_recovery_instruction(
+    client: &RpcClient,
+    program_id: &Pubkey,
+    rent_payer: &Pubkey,
+    cmd: VetoRecoveryCommand,
+) -> Result<Instruction> {
+    let slq_instance = get_instance(client, program_id, &cmd.instance_name)?;
+
+    if !slq_instance.admin_config.contains(rent_payer) {
+        bail!("{} is not an admin account of this instance", rent_payer);
+    }
+    if slq_instance.pending_recovery.is_none() {
+        bail!("instance has no pending recovery");
+    }
+
+    VetoRecovery::build_instruction(program_id, rent_payer, cmd.instance_name)
+}
+
+fn complete_recovery_instruction(
+    client: &RpcClient,
+    program_id: &Pubkey,
+    rent_payer: &Pubkey,
+    cmd: CompleteRecoveryCommand,
+) -> Result<Instruction> {
+    let slq_instance = get_instance(client, program_id, &cmd.instance_name)?;
+
+    let pending_recovery = match &slq_instance.pending_recovery {
+        Some(pending_recovery) => pending_recovery,
+        None => bail!("instance has no pending recovery"),
+    };
+    let timelock = slq_instance
+        .recovery
+        .as_ref()
+        .map_or(0, |recovery| recovery.timelock);
+    let completes_at = pending_recovery.started_at.saturating_add(timelock);
+    let now = get_clock(client)?.unix_timestamp;
+    if now < completes_at {
+        bail!("recovery completes at {}, it is {}", completes_at, now);
+    }
+
+    CompleteRecovery::build_instruction(program_id, rent_payer, cmd.instance_name)
+}
+
+fn get_instance(
+    client: &RpcClient,
+    program_id: &Pubkey,
+    instance_name: &str,
+) -> Result<SlqInstance> {
+    let (instance_pubkey, _) = make_instance_pda(program_id, instance_name);
+    let instance_account = client.get_account(&instance_pubkey)?;
+
+    Ok(SlqInstance::load(&instance_account.data)?)
+}
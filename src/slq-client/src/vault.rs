@@ -14,17 +14,123 @@ use std::str::FromStr;
 use structopt::StructOpt;
 
 use borsh::BorshDeserialize;
-use slq::admin::{AddAdminAccountAdmin, ChangeApprovalThresholdAdmin, RemoveAdminAccountAdmin};
-use slq::init;
 use slq::init::make_instance_pda;
-use slq::state::{AdminConfig, SlqInstance, MAX_ADMIN_ACCOUNTS};
+use slq::state::{ApprovalAction, Roles, SlqAccount, SlqInstance, SpendingLimit, Vault};
+use slq::validate;
+use slq::vault::{
+    make_legacy_vault_pda, make_vault_pda, CloseLegacyVault, CloseVault, CreateVault,
+    CreateVaultTokenAccount, DepositToVault, DepositTokensToVault, SetSpendingLimit,
+    WithdrawFromVault, WithdrawTokensFromVault,
+};
+use spl_associated_token_account::get_associated_token_address;
+
+use crate::admin::{check_admin_role, check_rent_payer_can_approve};
+use crate::proposal::get_clock;
 
-// todo: follow same pattern as in admin
 #[derive(StructOpt, Debug)]
 pub enum VaultCommand {
-    CreateVault,
-    DepositToVault { amount: u64 },
-    WithdrawFromVault { amount: u64 }, // todo: withdraw-all command
+    /// Create a vault governed by the instance, with admin approval.
+    CreateVault(CreateVaultCommand),
+    /// Move lamports from the rent payer into a vault.
+    DepositToVault(DepositToVaultCommand),
+    /// Move lamports out of a vault, with admin approval.
+    WithdrawFromVault(WithdrawFromVaultCommand), // todo: withdraw-all command
+    /// Create the vault's token account for a mint.
+    CreateTokenAccount(CreateTokenAccountCommand),
+    /// Move SPL tokens from the rent payer's token account into a vault.
+    DepositTokens(DepositTokensCommand),
+    /// Move SPL tokens out of a vault, with admin approval.
+    WithdrawTokens(WithdrawTokensCommand),
+    /// Let a single admin withdraw up to an amount per period, 0 to remove the limit.
+    SetSpendingLimit(SetSpendingLimitCommand),
+    /// Close a vault created before instances governed vaults,
+    /// as its creator, taking all its lamports.
+    CloseLegacyVault(CloseLegacyVaultCommand),
+    /// Close a vault and its empty token accounts, with admin approval,
+    /// taking all its lamports.
+    CloseVault(CloseVaultCommand),
+}
+
+#[derive(StructOpt, Debug)]
+pub struct CreateVaultCommand {
+    instance_name: String,
+    vault_name: String,
+}
+
+#[derive(StructOpt, Debug)]
+pub struct DepositToVaultCommand {
+    instance_name: String,
+    vault_name: String,
+    amount: u64,
+}
+
+#[derive(StructOpt, Debug)]
+pub struct WithdrawFromVaultCommand {
+    instance_name: String,
+    vault_name: String,
+    amount: u64,
+    /// The account receiving the lamports, the rent payer if omitted.
+    #[structopt(long)]
+    recipient: Option<String>,
+}
+
+#[derive(StructOpt, Debug)]
+pub struct CreateTokenAccountCommand {
+    instance_name: String,
+    vault_name: String,
+    mint: String,
+}
+
+#[derive(StructOpt, Debug)]
+pub struct DepositTokensCommand {
+    instance_name: String,
+    vault_name: String,
+    mint: String,
+    /// In the mint's smallest unit.
+    amount: u64,
+}
+
+#[derive(StructOpt, Debug)]
+pub struct WithdrawTokensCommand {
+    instance_name: String,
+    vault_name: String,
+    mint: String,
+    /// In the mint's smallest unit.
+    amount: u64,
+    /// The owner of the token account receiving the tokens, the rent payer if omitted.
+    #[structopt(long)]
+    recipient: Option<String>,
+}
+
+#[derive(StructOpt, Debug)]
+pub struct SetSpendingLimitCommand {
+    instance_name: String,
+    vault_name: String,
+    /// In lamports, or the mint's smallest unit.
+    amount: u64,
+    /// In seconds.
+    #[structopt(long, default_value = "86400")]
+    period: i64,
+    /// Limit withdrawals of this mint's tokens instead of lamports.
+    #[structopt(long)]
+    mint: Option<String>,
+}
+
+#[derive(StructOpt, Debug)]
+pub struct CloseLegacyVaultCommand {
+    vault_name: String,
+}
+
+#[derive(StructOpt, Debug)]
+pub struct CloseVaultCommand {
+    instance_name: String,
+    vault_name: String,
+    /// The account receiving the lamports, the rent payer if omitted.
+    #[structopt(long)]
+    recipient: Option<String>,
+    /// Also close the vault's token account for this mint, which must be empty.
+    #[structopt(long = "mint")]
+    mints: Vec<String>,
 }
 
 pub(crate) fn do_command(
@@ -33,24 +139,263 @@ pub(crate) fn do_command(
     rent_payer: &Pubkey,
     cmd: VaultCommand,
 ) -> Result<Instruction> {
-    let vault_name = "vault".to_string();
     match cmd {
-        VaultCommand::CreateVault => {
-            slq::vault::CreateVault::build_instruction(program_id, rent_payer, &vault_name)
+        VaultCommand::CreateVault(cmd) => {
+            let slq_instance = get_instance(client, program_id, &cmd.instance_name)?;
+            check_rent_payer_can_approve(&slq_instance, rent_payer, ApprovalAction::Default)?;
+
+            CreateVault::build_instruction(
+                program_id,
+                rent_payer,
+                &[*rent_payer],
+                &cmd.instance_name,
+                &cmd.vault_name,
+            )
+        }
+        VaultCommand::DepositToVault(cmd) => DepositToVault::build_instruction(
+            program_id,
+            rent_payer,
+            &cmd.instance_name,
+            &cmd.vault_name,
+            cmd.amount,
+        ),
+        VaultCommand::WithdrawFromVault(cmd) => {
+            check_rent_payer_can_withdraw(
+                client,
+                program_id,
+                rent_payer,
+                &cmd.instance_name,
+                &cmd.vault_name,
+                None,
+                cmd.amount,
+            )?;
+
+            let recipient = match cmd.recipient {
+                Some(recipient) => Pubkey::from_str(&recipient)?,
+                None => *rent_payer,
+            };
+
+            WithdrawFromVault::build_instruction(
+                program_id,
+                &[*rent_payer],
+                &cmd.instance_name,
+                &cmd.vault_name,
+                &recipient,
+                cmd.amount,
+            )
         }
-        VaultCommand::DepositToVault { amount } => slq::vault::DepositToVault::build_instruction(
+        VaultCommand::CreateTokenAccount(cmd) => CreateVaultTokenAccount::build_instruction(
             program_id,
             rent_payer,
-            &vault_name,
-            amount,
+            &cmd.instance_name,
+            &cmd.vault_name,
+            &Pubkey::from_str(&cmd.mint)?,
         ),
-        VaultCommand::WithdrawFromVault { amount } => {
-            slq::vault::WithdrawFromVault::build_instruction(
+        VaultCommand::DepositTokens(cmd) => {
+            let mint = Pubkey::from_str(&cmd.mint)?;
+            let source_token_account = get_associated_token_address(rent_payer, &mint);
+
+            DepositTokensToVault::build_instruction(
+                program_id,
+                rent_payer,
+                &source_token_account,
+                &cmd.instance_name,
+                &cmd.vault_name,
+                &mint,
+                cmd.amount,
+            )
+        }
+        VaultCommand::WithdrawTokens(cmd) => {
+            let mint = Pubkey::from_str(&cmd.mint)?;
+            check_rent_payer_can_withdraw(
+                client,
                 program_id,
                 rent_payer,
-                &vault_name,
-                amount,
+                &cmd.instance_name,
+                &cmd.vault_name,
+                Some(mint),
+                cmd.amount,
+            )?;
+
+            let recipient = match cmd.recipient {
+                Some(recipient) => Pubkey::from_str(&recipient)?,
+                None => *rent_payer,
+            };
+            let recipient_token_account = get_associated_token_address(&recipient, &mint);
+            if client.get_account(&recipient_token_account).is_err() {
+                bail!(
+                    "{} has no token account for mint {}, create {} first",
+                    recipient,
+                    mint,
+                    recipient_token_account
+                );
+            }
+
+            WithdrawTokensFromVault::build_instruction(
+                program_id,
+                &[*rent_payer],
+                &cmd.instance_name,
+                &cmd.vault_name,
+                &mint,
+                &recipient_token_account,
+                cmd.amount,
+            )
+        }
+        VaultCommand::SetSpendingLimit(cmd) => {
+            let slq_instance = get_instance(client, program_id, &cmd.instance_name)?;
+            let action = SetSpendingLimit::approval_action(&slq_instance.admin_config);
+            check_rent_payer_can_approve(&slq_instance, rent_payer, action)?;
+
+            let mint = cmd.mint.as_deref().map(Pubkey::from_str).transpose()?;
+            let vault = get_vault(client, program_id, &cmd.instance_name, &cmd.vault_name)?;
+            match cmd.amount {
+                0 if !vault
+                    .spending_limits
+                    .iter()
+                    .any(|spending_limit| spending_limit.mint == mint) =>
+                {
+                    bail!("vault has no spending limit to remove");
+                }
+                0 => {}
+                amount => validate::spending_limit(&SpendingLimit {
+                    mint,
+                    amount,
+                    period: cmd.period,
+                    period_start: 0,
+                    spent: 0,
+                })?,
+            }
+
+            SetSpendingLimit::build_instruction(
+                program_id,
+                rent_payer,
+                &[*rent_payer],
+                &cmd.instance_name,
+                &cmd.vault_name,
+                slq_instance.config_epoch,
+                mint,
+                cmd.amount,
+                cmd.period,
+            )
+        }
+        VaultCommand::CloseLegacyVault(cmd) => {
+            let (vault_pubkey, _) = make_legacy_vault_pda(program_id, rent_payer, &cmd.vault_name);
+            let vault = match client.get_account(&vault_pubkey) {
+                Ok(vault) => vault,
+                Err(_) => bail!("{} has no vault named {}", rent_payer, cmd.vault_name),
+            };
+            if !vault.data.is_empty() {
+                bail!(
+                    "vault {} stores data, it is not derived from its creator",
+                    vault_pubkey
+                );
+            }
+
+            CloseLegacyVault::build_instruction(program_id, rent_payer, &cmd.vault_name)
+        }
+        VaultCommand::CloseVault(cmd) => {
+            let slq_instance = get_instance(client, program_id, &cmd.instance_name)?;
+            if slq_instance.paused {
+                bail!("instance is paused, it must be unpaused first");
+            }
+            let action = CloseVault::approval_action(&slq_instance.admin_config);
+            check_rent_payer_can_approve(&slq_instance, rent_payer, action)?;
+
+            let (instance_pubkey, _) = make_instance_pda(program_id, &cmd.instance_name);
+            let (vault_pubkey, _) = make_vault_pda(program_id, &instance_pubkey, &cmd.vault_name);
+            let mints = cmd
+                .mints
+                .iter()
+                .map(|mint| Pubkey::from_str(mint))
+                .collect::<Result<Vec<_>, _>>()?;
+            for mint in &mints {
+                let vault_token_account = get_associated_token_address(&vault_pubkey, mint);
+                let balance = client
+                    .get_token_account_balance(&vault_token_account)
+                    .with_context(|| format!("vault has no token account for mint {}", mint))?;
+                if balance.amount != "0" {
+                    bail!(
+                        "vault holds {} of mint {}, withdraw them first",
+                        balance.ui_amount_string,
+                        mint
+                    );
+                }
+            }
+
+            let recipient = match cmd.recipient {
+                Some(recipient) => Pubkey::from_str(&recipient)?,
+                None => *rent_payer,
+            };
+
+            CloseVault::build_instruction(
+                program_id,
+                &[*rent_payer],
+                &cmd.instance_name,
+                &cmd.vault_name,
+                slq_instance.config_epoch,
+                &recipient,
+                &mints,
             )
         }
     }
 }
+
+/// Checks that `rent_payer` can approve a withdrawal on their own,
+/// either within the vault's spending limit or by meeting the withdrawal threshold.
+/// Spending limits don't apply while the instance has an execution delay.
+fn check_rent_payer_can_withdraw(
+    client: &RpcClient,
+    program_id: &Pubkey,
+    rent_payer: &Pubkey,
+    instance_name: &str,
+    vault_name: &str,
+    mint: Option<Pubkey>,
+    amount: u64,
+) -> Result<()> {
+    let slq_instance = get_instance(client, program_id, instance_name)?;
+    if slq_instance.paused {
+        bail!("instance is paused, it must be unpaused first");
+    }
+
+    let mut vault = get_vault(client, program_id, instance_name, vault_name)?;
+    let spending_limit = match slq_instance.admin_config.policy.execution_delay {
+        0 => vault.spending_limit_mut(mint),
+        _ => None,
+    };
+    if let Some(spending_limit) = spending_limit {
+        let now = get_clock(client)?.unix_timestamp;
+        if spending_limit.try_spend(now, amount) {
+            return check_admin_role(&slq_instance, rent_payer, Roles::APPROVER);
+        }
+    }
+
+    let action = match mint {
+        Some(_) => ApprovalAction::TokenWithdrawal,
+        None => ApprovalAction::VaultWithdrawal { amount },
+    };
+    check_rent_payer_can_approve(&slq_instance, rent_payer, action)
+}
+
+fn get_vault(
+    client: &RpcClient,
+    program_id: &Pubkey,
+    instance_name: &str,
+    vault_name: &str,
+) -> Result<Vault> {
+    let (instance_pubkey, _) = make_instance_pda(program_id, instance_name);
+    let (vault_pubkey, _) = make_vault_pda(program_id, &instance_pubkey, vault_name);
+    let vault_account = client.get_account(&vault_pubkey)?;
+
+    Ok(Vault::load(&vault_account.data)?)
+}
+
+fn get_instance(
+    client: &RpcClient,
+    program_id: &Pubkey,
+    instance_name: &str,
+) -> Result<SlqInstance> {
+    let (instance_pubkey, _) = make_instance_pda(program_id, instance_name);
+    let instance_account = client.get_account(&instance_pubkey)?;
+
+    Ok(SlqInstance::load(&instance_account.data)?)
+}
@@ -2,11 +2,13 @@
 
 use anyhow::{anyhow, bail, Result};
 use borsh::{BorshDeserialize, BorshSerialize};
-use solana_program::borsh::get_instance_packed_len;
+use solana_program::borsh1::get_instance_packed_len;
 use solana_program::instruction::{AccountMeta, Instruction};
+use solana_program::log::sol_log_data;
 use solana_program::sysvar::Sysvar;
 use solana_program::{
     account_info::{next_account_info, next_account_infos, AccountInfo},
+    bpf_loader_upgradeable,
     entrypoint::ProgramResult,
     msg,
     program::invoke_signed,
@@ -17,10 +19,23 @@ use solana_program::{
 };
 use std::convert::{TryFrom, TryInto};
 
-use crate::init::{create_admin_accounts_array, make_instance_pda, verify_pda};
+use crate::check;
+use crate::error::SlqError;
+use crate::init::{
+    close_account, invoke_as_authority, make_authority_pda, make_instance_pda, resize_account,
+    verify_authority_pda, verify_pda,
+};
+use crate::state::AdminAccount;
 use crate::state::AdminConfig;
+use crate::state::AdminKind;
+use crate::state::ApprovalAction;
+use crate::state::ApprovalPolicy;
+use crate::state::RecoveryConfig;
+use crate::state::Roles;
+use crate::state::SlqAccount;
 use crate::state::SlqInstance;
-use crate::state::MAX_ADMIN_ACCOUNTS;
+use crate::state::StoredInstruction;
+use crate::validate;
 use crate::SlqInstruction;
 
 pub fn exec(
@@ -30,8 +45,19 @@ pub fn exec(
 ) -> ProgramResult {
     match instr {
         SlqAdminInstruction::ChangeApprovalThreshold(instr) => instr.exec(program_id, accounts),
-        SlqAdminInstruction::AddAdminAccount(instr) => instr.exec(program_id, accounts),
+        SlqAdminInstruction::AddAdminAccount(instr) => instr.exec(),
         SlqAdminInstruction::RemoveAdminAccount(instr) => instr.exec(program_id, accounts),
+        SlqAdminInstruction::Invoke(instr) => instr.exec(program_id, accounts),
+        SlqAdminInstruction::DestroyInstance(instr) => instr.exec(program_id, accounts),
+        SlqAdminInstruction::SetApprovalPolicy(instr) => instr.exec(program_id, accounts),
+        SlqAdminInstruction::SetAdminConfig(instr) => instr.exec(program_id, accounts),
+        SlqAdminInstruction::AcceptAdminAccount(instr) => instr.exec(program_id, accounts),
+        SlqAdminInstruction::RotateAdminKey(instr) => instr.exec(program_id, accounts),
+        SlqAdminInstruction::Pause(instr) => instr.exec(program_id, accounts),
+        SlqAdminInstruction::Unpause(instr) => instr.exec(program_id, accounts),
+        SlqAdminInstruction::SetRecoveryConfig(instr) => instr.exec(program_id, accounts),
+        SlqAdminInstruction::NominateAdminAccount(instr) => instr.exec(program_id, accounts),
+        SlqAdminInstruction::SetGuardians(instr) => instr.exec(program_id, accounts),
     }
 }
 
@@ -40,25 +66,39 @@ pub enum SlqAdminInstruction {
     ChangeApprovalThreshold(ChangeApprovalThresholdAdmin),
     AddAdminAccount(AddAdminAccountAdmin),
     RemoveAdminAccount(RemoveAdminAccountAdmin),
+    Invoke(InvokeAdmin),
+    DestroyInstance(DestroyInstanceAdmin),
+    SetApprovalPolicy(SetApprovalPolicyAdmin),
+    SetAdminConfig(SetAdminConfigAdmin),
+    AcceptAdminAccount(AcceptAdminAccountAdmin),
+    RotateAdminKey(RotateAdminKeyAdmin),
+    Pause(PauseAdmin),
+    Unpause(UnpauseAdmin),
+    SetRecoveryConfig(SetRecoveryConfigAdmin),
+    NominateAdminAccount(NominateAdminAccountAdmin),
+    SetGuardians(SetGuardiansAdmin),
 }
 
 /// # Accounts
 ///
 /// - 0: rent_payer - writable, signer
 /// - 1: instance_pda - pda, writable
+/// - 2..: admin_accounts - signer, distinct admins whose weights meet the threshold-change threshold
 #[derive(BorshSerialize, BorshDeserialize, Debug)]
 pub struct ChangeApprovalThresholdAdmin {
     instance_name: String,
+    expected_config_epoch: u64,
     approval_threshold: u8,
     instance_pda_bump_seed: u8,
 }
 
 impl ChangeApprovalThresholdAdmin {
-    pub fn build_instruction_with_admin_accounts(
+    pub fn build_instruction(
         program_id: &Pubkey,
         rent_payer: &Pubkey,
         admin_pubkeys: &[Pubkey],
         instance_name: String,
+        expected_config_epoch: u64,
         approval_threshold: u8,
     ) -> Result<Instruction> {
         let (instance_pda, instance_pda_bump_seed) = make_instance_pda(program_id, &instance_name);
@@ -66,6 +106,7 @@ impl ChangeApprovalThresholdAdmin {
         let instr = SlqInstruction::Admin(SlqAdminInstruction::ChangeApprovalThreshold(
             ChangeApprovalThresholdAdmin {
                 instance_name,
+                expected_config_epoch,
                 approval_threshold,
                 instance_pda_bump_seed,
             },
@@ -79,35 +120,135 @@ impl ChangeApprovalThresholdAdmin {
         accounts.extend(
             admin_pubkeys
                 .iter()
-                .map(|admin_pubkey| AccountMeta::new(*admin_pubkey, true)),
+                .map(|admin_pubkey| AccountMeta::new_readonly(*admin_pubkey, true)),
         );
 
-        msg!("admin_pubkeys: {:#?}", accounts);
-
         Ok(Instruction::new_with_borsh(*program_id, &instr, accounts))
     }
 
+    fn exec(&self, program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+
+        let rent_payer = next_account_info(accounts_iter)?;
+        let instance_pda = next_account_info(accounts_iter)?;
+
+        {
+            check::writable(rent_payer, "rent_payer")?;
+            check::signer(rent_payer, "rent_payer")?;
+            check::writable(instance_pda, "instance_pda")?;
+            check::owner(instance_pda, program_id, "instance_pda")?;
+
+            verify_pda(
+                program_id,
+                &self.instance_name,
+                instance_pda.key,
+                self.instance_pda_bump_seed,
+                make_instance_pda,
+            )?;
+        }
+
+        let mut instance = SlqInstance::load(&instance_pda.data.borrow())?;
+        verify_config_epoch(&instance, self.expected_config_epoch)?;
+
+        verify_admin_approval(
+            program_id,
+            instance_pda.key,
+            &instance.admin_config,
+            ApprovalAction::ThresholdChange,
+            accounts_iter.as_slice(),
+        )?;
+        instance.admin_config.approval_threshold = self.approval_threshold;
+        validate::admin_config(&instance.admin_config)?;
+        instance.bump_config_epoch()?;
+        instance.record_admin_action()?;
+        instance.store(&mut instance_pda.data.borrow_mut())?;
+
+        Ok(())
+    }
+}
+
+/// Admins are no longer added in one step, see `NominateAdminAccountAdmin`.
+///
+/// The variant keeps its place so that the other admin instructions
+/// keep their encoding, and is always refused.
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct AddAdminAccountAdmin {
+    instance_name: String,
+    new_admin_account: Pubkey,
+    instance_pda_bump_seed: u8,
+}
+
+impl AddAdminAccountAdmin {
+    fn exec(&self) -> ProgramResult {
+        msg!("admins must be nominated, then accept the nomination");
+        Err(SlqError::NotNominated.into())
+    }
+}
+
+/// Nominates an admin, replacing any earlier nomination.
+///
+/// The nominee only joins the admin set once it signs `AcceptAdminAccountAdmin`,
+/// so a mistyped key can never become an admin.
+/// The config epoch only moves on acceptance, when the admin set changes.
+/// The instance account grows to fit the nomination,
+/// with the extra rent paid by `rent_payer`.
+///
+/// # Accounts
+///
+/// - 0: rent_payer - writable, signer
+/// - 1: instance_pda - pda, writable
+/// - 2: system_program - executable
+/// - 3..: admin_accounts - signer, distinct admins whose weights meet the admin-set threshold
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct NominateAdminAccountAdmin {
+    instance_name: String,
+    expected_config_epoch: u64,
+    new_admin_account: Pubkey,
+    new_admin_weight: u8,
+    new_admin_roles: Roles,
+    new_admin_kind: AdminKind,
+    instance_pda_bump_seed: u8,
+}
+
+impl NominateAdminAccountAdmin {
+    #[allow(clippy::too_many_arguments)]
     pub fn build_instruction(
         program_id: &Pubkey,
         rent_payer: &Pubkey,
+        admin_pubkeys: &[Pubkey],
         instance_name: String,
-        approval_threshold: u8,
+        expected_config_epoch: u64,
+        new_admin_account: Pubkey,
+        new_admin_weight: u8,
+        new_admin_roles: Roles,
+        new_admin_kind: AdminKind,
     ) -> Result<Instruction> {
         let (instance_pda, instance_pda_bump_seed) = make_instance_pda(program_id, &instance_name);
 
-        let instr = SlqInstruction::Admin(SlqAdminInstruction::ChangeApprovalThreshold(
-            ChangeApprovalThresholdAdmin {
+        let instr = SlqInstruction::Admin(SlqAdminInstruction::NominateAdminAccount(
+            NominateAdminAccountAdmin {
                 instance_name,
-                approval_threshold,
+                expected_config_epoch,
+                new_admin_account,
+                new_admin_weight,
+                new_admin_roles,
+                new_admin_kind,
                 instance_pda_bump_seed,
             },
         ));
 
-        let accounts = vec![
+        let mut accounts = vec![
             AccountMeta::new(*rent_payer, true),
             AccountMeta::new(instance_pda, false),
+            AccountMeta::new_readonly(system_program::ID, false),
         ];
 
+        accounts.extend(
+            admin_pubkeys
+                .iter()
+                .map(|admin_pubkey| AccountMeta::new_readonly(*admin_pubkey, true)),
+        );
+
         Ok(Instruction::new_with_borsh(*program_id, &instr, accounts))
     }
 
@@ -116,12 +257,14 @@ impl ChangeApprovalThresholdAdmin {
 
         let rent_payer = next_account_info(accounts_iter)?;
         let instance_pda = next_account_info(accounts_iter)?;
+        let system_program = next_account_info(accounts_iter)?;
 
         {
-            assert!(rent_payer.is_writable);
-            assert!(rent_payer.is_signer);
-            assert!(instance_pda.is_writable);
-            assert_eq!(instance_pda.owner, program_id, "unexpected program id");
+            check::writable(rent_payer, "rent_payer")?;
+            check::signer(rent_payer, "rent_payer")?;
+            check::writable(instance_pda, "instance_pda")?;
+            check::owner(instance_pda, program_id, "instance_pda")?;
+            check::system_program(system_program)?;
 
             verify_pda(
                 program_id,
@@ -129,46 +272,82 @@ impl ChangeApprovalThresholdAdmin {
                 instance_pda.key,
                 self.instance_pda_bump_seed,
                 make_instance_pda,
-            );
+            )?;
         }
 
-        let mut instance = SlqInstance::try_from_slice(&instance_pda.data.borrow_mut())?;
-        instance.admin_config.approval_threshold = self.approval_threshold;
-        instance.serialize(&mut *instance_pda.data.borrow_mut())?;
+        let mut instance = SlqInstance::load(&instance_pda.data.borrow())?;
+        verify_config_epoch(&instance, self.expected_config_epoch)?;
+
+        verify_admin_approval(
+            program_id,
+            instance_pda.key,
+            &instance.admin_config,
+            ApprovalAction::AdminSetChange,
+            accounts_iter.as_slice(),
+        )?;
+
+        let nominee = AdminAccount {
+            pubkey: self.new_admin_account,
+            weight: self.new_admin_weight,
+            roles: self.new_admin_roles,
+            kind: self.new_admin_kind,
+        };
+
+        // The nominee must be acceptable as things stand now,
+        // accepting re-validates in case the config changes meanwhile.
+        let mut admin_config = instance.admin_config.clone();
+        admin_config.admin_accounts.push(nominee);
+        validate::admin_config(&admin_config)?;
+
+        instance.pending_admin = Some(nominee);
+
+        resize_account(
+            instance_pda,
+            rent_payer,
+            system_program,
+            instance.packed_len()?,
+        )?;
+        instance.record_admin_action()?;
+        instance.store(&mut instance_pda.data.borrow_mut())?;
 
         Ok(())
     }
 }
 
+/// Adds the pending admin to the admin set, signed by the nominee itself.
+///
+/// A nominated instance signs with its authority pda, through a proposal or admin invoke
+/// approved by its own admins.
+///
+/// The instance account keeps its size, as the nomination makes room for the admin.
+///
 /// # Accounts
 ///
-/// - 0: rent_payer - writable, signer
+/// - 0: new_admin - signer
 /// - 1: instance_pda - pda, writable
 #[derive(BorshSerialize, BorshDeserialize, Debug)]
-pub struct AddAdminAccountAdmin {
+pub struct AcceptAdminAccountAdmin {
     instance_name: String,
-    new_admin_account: Pubkey,
     instance_pda_bump_seed: u8,
 }
 
-impl AddAdminAccountAdmin {
+impl AcceptAdminAccountAdmin {
     pub fn build_instruction(
         program_id: &Pubkey,
-        rent_payer: &Pubkey,
+        new_admin: &Pubkey,
         instance_name: String,
-        new_admin_account: Pubkey,
     ) -> Result<Instruction> {
         let (instance_pda, instance_pda_bump_seed) = make_instance_pda(program_id, &instance_name);
 
-        let instr =
-            SlqInstruction::Admin(SlqAdminInstruction::AddAdminAccount(AddAdminAccountAdmin {
+        let instr = SlqInstruction::Admin(SlqAdminInstruction::AcceptAdminAccount(
+            AcceptAdminAccountAdmin {
                 instance_name,
-                new_admin_account,
                 instance_pda_bump_seed,
-            }));
+            },
+        ));
 
         let accounts = vec![
-            AccountMeta::new(*rent_payer, true),
+            AccountMeta::new_readonly(*new_admin, true),
             AccountMeta::new(instance_pda, false),
         ];
 
@@ -178,14 +357,13 @@ impl AddAdminAccountAdmin {
     fn exec(&self, program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
         let accounts_iter = &mut accounts.iter();
 
-        let rent_payer = next_account_info(accounts_iter)?;
+        let new_admin = next_account_info(accounts_iter)?;
         let instance_pda = next_account_info(accounts_iter)?;
 
         {
-            assert!(rent_payer.is_writable);
-            assert!(rent_payer.is_signer);
-            assert!(instance_pda.is_writable);
-            assert_eq!(instance_pda.owner, program_id, "unexpected program id");
+            check::signer(new_admin, "new_admin")?;
+            check::writable(instance_pda, "instance_pda")?;
+            check::owner(instance_pda, program_id, "instance_pda")?;
 
             verify_pda(
                 program_id,
@@ -193,36 +371,42 @@ impl AddAdminAccountAdmin {
                 instance_pda.key,
                 self.instance_pda_bump_seed,
                 make_instance_pda,
-            );
+            )?;
         }
 
-        let mut instance = SlqInstance::try_from_slice(&instance_pda.data.borrow_mut())?;
+        let mut instance = SlqInstance::load(&instance_pda.data.borrow())?;
 
-        let mut admin_accounts = instance
-            .admin_config
-            .admin_accounts
-            .iter()
-            .filter(|account| **account != Pubkey::default())
-            .copied()
-            .collect::<Vec<Pubkey>>();
-
-        admin_accounts.push(self.new_admin_account);
+        let nominee = match instance.pending_admin {
+            Some(nominee) if nominee.signer(program_id) == Some(*new_admin.key) => nominee,
+            _ => {
+                msg!("{} is not the pending admin", new_admin.key);
+                return Err(SlqError::NotNominated.into());
+            }
+        };
 
-        instance.admin_config.admin_accounts = create_admin_accounts_array(&admin_accounts);
-
-        instance.serialize(&mut *instance_pda.data.borrow_mut())?;
+        instance.admin_config.admin_accounts.push(nominee);
+        validate::admin_config(&instance.admin_config)?;
+        instance.pending_admin = None;
+        instance.bump_config_epoch()?;
+        instance.record_admin_action()?;
+        instance.store(&mut instance_pda.data.borrow_mut())?;
 
         Ok(())
     }
 }
 
+/// The instance account shrinks, refunding the freed rent to `rent_payer`.
+///
 /// # Accounts
 ///
 /// - 0: rent_payer - writable, signer
 /// - 1: instance_pda - pda, writable
+/// - 2: system_program - executable
+/// - 3..: admin_accounts - signer, distinct admins whose weights meet the admin-set threshold
 #[derive(BorshSerialize, BorshDeserialize, Debug)]
 pub struct RemoveAdminAccountAdmin {
     instance_name: String,
+    expected_config_epoch: u64,
     to_remove_admin_account: Pubkey,
     instance_pda_bump_seed: u8,
 }
@@ -231,7 +415,9 @@ impl RemoveAdminAccountAdmin {
     pub fn build_instruction(
         program_id: &Pubkey,
         rent_payer: &Pubkey,
+        admin_pubkeys: &[Pubkey],
         instance_name: String,
+        expected_config_epoch: u64,
         to_remove_admin_account: Pubkey,
     ) -> Result<Instruction> {
         let (instance_pda, instance_pda_bump_seed) = make_instance_pda(program_id, &instance_name);
@@ -239,16 +425,24 @@ impl RemoveAdminAccountAdmin {
         let instr = SlqInstruction::Admin(SlqAdminInstruction::RemoveAdminAccount(
             RemoveAdminAccountAdmin {
                 instance_name,
+                expected_config_epoch,
                 to_remove_admin_account,
                 instance_pda_bump_seed,
             },
         ));
 
-        let accounts = vec![
+        let mut accounts = vec![
             AccountMeta::new(*rent_payer, true),
             AccountMeta::new(instance_pda, false),
+            AccountMeta::new_readonly(system_program::ID, false),
         ];
 
+        accounts.extend(
+            admin_pubkeys
+                .iter()
+                .map(|admin_pubkey| AccountMeta::new_readonly(*admin_pubkey, true)),
+        );
+
         Ok(Instruction::new_with_borsh(*program_id, &instr, accounts))
     }
 
@@ -257,12 +451,14 @@ impl RemoveAdminAccountAdmin {
 
         let rent_payer = next_account_info(accounts_iter)?;
         let instance_pda = next_account_info(accounts_iter)?;
+        let system_program = next_account_info(accounts_iter)?;
 
         {
-            assert!(rent_payer.is_writable);
-            assert!(rent_payer.is_signer);
-            assert!(instance_pda.is_writable);
-            assert_eq!(instance_pda.owner, program_id, "unexpected program id");
+            check::writable(rent_payer, "rent_payer")?;
+            check::signer(rent_payer, "rent_payer")?;
+            check::writable(instance_pda, "instance_pda")?;
+            check::owner(instance_pda, program_id, "instance_pda")?;
+            check::system_program(system_program)?;
 
             verify_pda(
                 program_id,
@@ -270,25 +466,1260 @@ impl RemoveAdminAccountAdmin {
                 instance_pda.key,
                 self.instance_pda_bump_seed,
                 make_instance_pda,
-            );
+            )?;
         }
 
-        let mut instance = SlqInstance::try_from_slice(&instance_pda.data.borrow_mut())?;
+        let mut instance = SlqInstance::load(&instance_pda.data.borrow())?;
+        verify_config_epoch(&instance, self.expected_config_epoch)?;
+
+        verify_admin_approval(
+            program_id,
+            instance_pda.key,
+            &instance.admin_config,
+            ApprovalAction::AdminSetChange,
+            accounts_iter.as_slice(),
+        )?;
 
-        let mut admin_accounts = instance
+        let index = match instance
             .admin_config
-            .admin_accounts
-            .iter()
-            .filter(|account| {
-                **account != Pubkey::default() && **account != self.to_remove_admin_account
-            })
-            .copied()
-            .collect::<Vec<Pubkey>>();
+            .position(&self.to_remove_admin_account)
+        {
+            Some(index) => index,
+            None => {
+                msg!("account {} is not an admin", self.to_remove_admin_account);
+                return Err(SlqError::NotAnAdmin.into());
+            }
+        };
+        instance.admin_config.admin_accounts.remove(index);
+        validate::admin_config(&instance.admin_config)?;
+        instance.bump_config_epoch()?;
+
+        resize_account(
+            instance_pda,
+            rent_payer,
+            system_program,
+            instance.packed_len()?,
+        )?;
+        instance.record_admin_action()?;
+        instance.store(&mut instance_pda.data.borrow_mut())?;
+
+        Ok(())
+    }
+}
+
+/// Replaces an admin's key in place, keeping its weight, roles and position.
+///
+/// The old key, the new key and the admin-set threshold must all approve,
+/// so an admin can only be rotated to a key that is known to sign.
+/// Instance admins sign with their authority pda, which has no key to rotate.
+///
+/// # Accounts
+///
+/// - 0: rent_payer - writable, signer
+/// - 1: instance_pda - pda, writable
+/// - 2: old_admin_account - signer
+/// - 3: new_admin_account - signer
+/// - 4..: admin_accounts - signer, distinct admins whose weights meet the admin-set threshold
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct RotateAdminKeyAdmin {
+    instance_name: String,
+    expected_config_epoch: u64,
+    instance_pda_bump_seed: u8,
+}
+
+impl RotateAdminKeyAdmin {
+    pub fn build_instruction(
+        program_id: &Pubkey,
+        rent_payer: &Pubkey,
+        admin_pubkeys: &[Pubkey],
+        instance_name: String,
+        expected_config_epoch: u64,
+        old_admin_account: &Pubkey,
+        new_admin_account: &Pubkey,
+    ) -> Result<Instruction> {
+        let (instance_pda, instance_pda_bump_seed) = make_instance_pda(program_id, &instance_name);
+
+        let instr =
+            SlqInstruction::Admin(SlqAdminInstruction::RotateAdminKey(RotateAdminKeyAdmin {
+                instance_name,
+                expected_config_epoch,
+                instance_pda_bump_seed,
+            }));
+
+        let mut accounts = vec![
+            AccountMeta::new(*rent_payer, true),
+            AccountMeta::new(instance_pda, false),
+            AccountMeta::new_readonly(*old_admin_account, true),
+            AccountMeta::new_readonly(*new_admin_account, true),
+        ];
+
+        accounts.extend(
+            admin_pubkeys
+                .iter()
+                .map(|admin_pubkey| AccountMeta::new_readonly(*admin_pubkey, true)),
+        );
+
+        Ok(Instruction::new_with_borsh(*program_id, &instr, accounts))
+    }
+
+    fn exec(&self, program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+
+        let rent_payer = next_account_info(accounts_iter)?;
+        let instance_pda = next_account_info(accounts_iter)?;
+        let old_admin_account = next_account_info(accounts_iter)?;
+        let new_admin_account = next_account_info(accounts_iter)?;
+
+        {
+            check::writable(rent_payer, "rent_payer")?;
+            check::signer(rent_payer, "rent_payer")?;
+            check::writable(instance_pda, "instance_pda")?;
+            check::owner(instance_pda, program_id, "instance_pda")?;
+            check::signer(old_admin_account, "old_admin_account")?;
+            check::signer(new_admin_account, "new_admin_account")?;
+
+            verify_pda(
+                program_id,
+                &self.instance_name,
+                instance_pda.key,
+                self.instance_pda_bump_seed,
+                make_instance_pda,
+            )?;
+        }
+
+        let mut instance = SlqInstance::load(&instance_pda.data.borrow())?;
+        verify_config_epoch(&instance, self.expected_config_epoch)?;
 
-        instance.admin_config.admin_accounts = create_admin_accounts_array(&admin_accounts);
+        verify_admin_approval(
+            program_id,
+            instance_pda.key,
+            &instance.admin_config,
+            ApprovalAction::AdminSetChange,
+            accounts_iter.as_slice(),
+        )?;
 
-        instance.serialize(&mut *instance_pda.data.borrow_mut())?;
+        let index = match instance
+            .admin_config
+            .signer_position(program_id, old_admin_account.key)
+        {
+            Some(index) => index,
+            None => {
+                msg!("account {} is not an admin", old_admin_account.key);
+                return Err(SlqError::NotAnAdmin.into());
+            }
+        };
+        let admin_account = &mut instance.admin_config.admin_accounts[index];
+        if admin_account.kind != AdminKind::Key {
+            msg!(
+                "admin {} is an instance, its key can't be rotated",
+                admin_account.pubkey
+            );
+            return Err(SlqError::WrongAdminKind.into());
+        }
+        admin_account.pubkey = *new_admin_account.key;
+        validate::admin_config(&instance.admin_config)?;
+        instance.bump_config_epoch()?;
+        instance.record_admin_action()?;
+        instance.store(&mut instance_pda.data.borrow_mut())?;
 
         Ok(())
     }
 }
+
+/// Invokes an arbitrary instruction signed by the instance's authority pda.
+///
+/// The threshold is that of `invoke_action` for the instruction.
+///
+/// # Accounts
+///
+/// - 0: rent_payer - writable, signer
+/// - 1: instance_pda - pda
+/// - 2..2+admin_signer_count: admin_accounts - signer
+/// - ..: every account and program referenced by `instruction`
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct InvokeAdmin {
+    instance_name: String,
+    expected_config_epoch: u64,
+    instruction: StoredInstruction,
+    admin_signer_count: u8,
+    instance_pda_bump_seed: u8,
+    authority_pda_bump_seed: u8,
+}
+
+impl InvokeAdmin {
+    pub fn build_instruction(
+        program_id: &Pubkey,
+        rent_payer: &Pubkey,
+        admin_pubkeys: &[Pubkey],
+        instance_name: String,
+        expected_config_epoch: u64,
+        instruction: &Instruction,
+    ) -> Result<Instruction> {
+        let (instance_pda, instance_pda_bump_seed) = make_instance_pda(program_id, &instance_name);
+        let (authority_pda, authority_pda_bump_seed) =
+            make_authority_pda(program_id, &instance_pda);
+        let admin_signer_count = u8::try_from(admin_pubkeys.len())?;
+
+        let instr = SlqInstruction::Admin(SlqAdminInstruction::Invoke(InvokeAdmin {
+            instance_name,
+            expected_config_epoch,
+            instruction: StoredInstruction::from(instruction),
+            admin_signer_count,
+            instance_pda_bump_seed,
+            authority_pda_bump_seed,
+        }));
+
+        let mut accounts = vec![
+            AccountMeta::new(*rent_payer, true),
+            AccountMeta::new_readonly(instance_pda, false),
+        ];
+
+        accounts.extend(
+            admin_pubkeys
+                .iter()
+                .map(|admin_pubkey| AccountMeta::new_readonly(*admin_pubkey, true)),
+        );
+
+        accounts.push(AccountMeta::new_readonly(instruction.program_id, false));
+        accounts.extend(instruction.accounts.iter().map(|meta| AccountMeta {
+            pubkey: meta.pubkey,
+            is_signer: meta.is_signer && meta.pubkey != authority_pda,
+            is_writable: meta.is_writable,
+        }));
+
+        Ok(Instruction::new_with_borsh(*program_id, &instr, accounts))
+    }
+
+    fn exec(&self, program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+
+        let rent_payer = next_account_info(accounts_iter)?;
+        let instance_pda = next_account_info(accounts_iter)?;
+        let admin_signers =
+            next_account_infos(accounts_iter, usize::from(self.admin_signer_count))?;
+
+        {
+            check::writable(rent_payer, "rent_payer")?;
+            check::signer(rent_payer, "rent_payer")?;
+            check::owner(instance_pda, program_id, "instance_pda")?;
+
+            verify_pda(
+                program_id,
+                &self.instance_name,
+                instance_pda.key,
+                self.instance_pda_bump_seed,
+                make_instance_pda,
+            )?;
+            verify_authority_pda(program_id, instance_pda.key, self.authority_pda_bump_seed)?;
+        }
+
+        let instance = SlqInstance::load(&instance_pda.data.borrow())?;
+        verify_config_epoch(&instance, self.expected_config_epoch)?;
+        verify_invoke_allowed(program_id, &instance, Some(&self.instruction))?;
+
+        let action = invoke_action(
+            program_id,
+            &instance.admin_config,
+            Some(&self.instruction),
+            ApprovalAction::Default,
+        );
+        verify_admin_approval(
+            program_id,
+            instance_pda.key,
+            &instance.admin_config,
+            action,
+            admin_signers,
+        )?;
+
+        invoke_as_authority(
+            &Instruction::from(&self.instruction),
+            accounts,
+            instance_pda.key,
+            self.authority_pda_bump_seed,
+        )
+    }
+}
+
+/// Closes the instance, returning its rent to `recipient`.
+///
+/// Fails while the instance has open proposals or vaults,
+/// or while its authority pda holds lamports.
+///
+/// The authority pda must not be the authority over any other account either,
+/// which slq can't check: a new instance of the same name
+/// derives the same authority pda, and would take that authority over.
+///
+/// # Accounts
+///
+/// - 0: rent_payer - writable, signer
+/// - 1: instance_pda - pda, writable
+/// - 2: recipient - writable
+/// - 3: authority_pda - pda, the instance's authority
+/// - 4..: admin_accounts - signer, distinct admins whose weights meet `approval_threshold`
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct DestroyInstanceAdmin {
+    instance_name: String,
+    expected_config_epoch: u64,
+    instance_pda_bump_seed: u8,
+}
+
+impl DestroyInstanceAdmin {
+    pub fn build_instruction(
+        program_id: &Pubkey,
+        rent_payer: &Pubkey,
+        admin_pubkeys: &[Pubkey],
+        instance_name: String,
+        expected_config_epoch: u64,
+        recipient: &Pubkey,
+    ) -> Result<Instruction> {
+        let (instance_pda, instance_pda_bump_seed) = make_instance_pda(program_id, &instance_name);
+        let (authority_pda, _) = make_authority_pda(program_id, &instance_pda);
+
+        let instr =
+            SlqInstruction::Admin(SlqAdminInstruction::DestroyInstance(DestroyInstanceAdmin {
+                instance_name,
+                expected_config_epoch,
+                instance_pda_bump_seed,
+            }));
+
+        let mut accounts = vec![
+            AccountMeta::new(*rent_payer, true),
+            AccountMeta::new(instance_pda, false),
+            AccountMeta::new(*recipient, false),
+            AccountMeta::new_readonly(authority_pda, false),
+        ];
+
+        accounts.extend(
+            admin_pubkeys
+                .iter()
+                .map(|admin_pubkey| AccountMeta::new_readonly(*admin_pubkey, true)),
+        );
+
+        Ok(Instruction::new_with_borsh(*program_id, &instr, accounts))
+    }
+
+    fn exec(&self, program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+
+        let rent_payer = next_account_info(accounts_iter)?;
+        let instance_pda = next_account_info(accounts_iter)?;
+        let recipient = next_account_info(accounts_iter)?;
+        let authority_pda = next_account_info(accounts_iter)?;
+
+        {
+            check::writable(rent_payer, "rent_payer")?;
+            check::signer(rent_payer, "rent_payer")?;
+            check::writable(instance_pda, "instance_pda")?;
+            check::owner(instance_pda, program_id, "instance_pda")?;
+            check::writable(recipient, "recipient")?;
+
+            verify_pda(
+                program_id,
+                &self.instance_name,
+                instance_pda.key,
+                self.instance_pda_bump_seed,
+                make_instance_pda,
+            )?;
+
+            let (expected_authority_pda, _) = make_authority_pda(program_id, instance_pda.key);
+            if *authority_pda.key != expected_authority_pda {
+                msg!(
+                    "expected authority pda {}, got {}",
+                    expected_authority_pda,
+                    authority_pda.key
+                );
+                return Err(SlqError::WrongPda.into());
+            }
+        }
+
+        let instance = SlqInstance::load(&instance_pda.data.borrow())?;
+        verify_config_epoch(&instance, self.expected_config_epoch)?;
+
+        verify_admin_approval(
+            program_id,
+            instance_pda.key,
+            &instance.admin_config,
+            ApprovalAction::Default,
+            accounts_iter.as_slice(),
+        )?;
+
+        if instance.open_proposals > 0 {
+            msg!(
+                "instance has {} open proposals, execute them first",
+                instance.open_proposals
+            );
+            return Err(SlqError::InstanceInUse.into());
+        }
+
+        if instance.vaults > 0 {
+            msg!("instance has {} vaults, close them first", instance.vaults);
+            return Err(SlqError::InstanceInUse.into());
+        }
+
+        if authority_pda.lamports() > 0 {
+            msg!(
+                "authority {} holds {} lamports, which would outlive the instance",
+                authority_pda.key,
+                authority_pda.lamports()
+            );
+            return Err(SlqError::InstanceInUse.into());
+        }
+
+        close_account(instance_pda, recipient)
+    }
+}
+
+/// Replaces the instance's per-action approval thresholds.
+///
+/// Approved under the threshold-change threshold of the current policy.
+///
+/// # Accounts
+///
+/// - 0: rent_payer - writable, signer
+/// - 1: instance_pda - pda, writable
+/// - 2..: admin_accounts - signer, distinct admins whose weights meet the threshold-change threshold
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct SetApprovalPolicyAdmin {
+    instance_name: String,
+    expected_config_epoch: u64,
+    policy: ApprovalPolicy,
+    instance_pda_bump_seed: u8,
+}
+
+impl SetApprovalPolicyAdmin {
+    pub fn build_instruction(
+        program_id: &Pubkey,
+        rent_payer: &Pubkey,
+        admin_pubkeys: &[Pubkey],
+        instance_name: String,
+        expected_config_epoch: u64,
+        policy: ApprovalPolicy,
+    ) -> Result<Instruction> {
+        let (instance_pda, instance_pda_bump_seed) = make_instance_pda(program_id, &instance_name);
+
+        let instr = SlqInstruction::Admin(SlqAdminInstruction::SetApprovalPolicy(
+            SetApprovalPolicyAdmin {
+                instance_name,
+                expected_config_epoch,
+                policy,
+                instance_pda_bump_seed,
+            },
+        ));
+
+        let mut accounts = vec![
+            AccountMeta::new(*rent_payer, true),
+            AccountMeta::new(instance_pda, false),
+        ];
+
+        accounts.extend(
+            admin_pubkeys
+                .iter()
+                .map(|admin_pubkey| AccountMeta::new_readonly(*admin_pubkey, true)),
+        );
+
+        Ok(Instruction::new_with_borsh(*program_id, &instr, accounts))
+    }
+
+    fn exec(&self, program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+
+        let rent_payer = next_account_info(accounts_iter)?;
+        let instance_pda = next_account_info(accounts_iter)?;
+
+        {
+            check::writable(rent_payer, "rent_payer")?;
+            check::signer(rent_payer, "rent_payer")?;
+            check::writable(instance_pda, "instance_pda")?;
+            check::owner(instance_pda, program_id, "instance_pda")?;
+
+            verify_pda(
+                program_id,
+                &self.instance_name,
+                instance_pda.key,
+                self.instance_pda_bump_seed,
+                make_instance_pda,
+            )?;
+        }
+
+        let mut instance = SlqInstance::load(&instance_pda.data.borrow())?;
+        verify_config_epoch(&instance, self.expected_config_epoch)?;
+
+        verify_admin_approval(
+            program_id,
+            instance_pda.key,
+            &instance.admin_config,
+            ApprovalAction::ThresholdChange,
+            accounts_iter.as_slice(),
+        )?;
+        instance.admin_config.policy = self.policy;
+        validate::admin_config(&instance.admin_config)?;
+        instance.bump_config_epoch()?;
+        instance.record_admin_action()?;
+        instance.store(&mut instance_pda.data.borrow_mut())?;
+
+        Ok(())
+    }
+}
+
+/// Pauses the instance in an emergency, on the say of a single guardian.
+///
+/// A guardian is either one of the instance's guardians
+/// or an admin with the guardian role.
+///
+/// # Accounts
+///
+/// - 0: guardian - signer, a guardian or an admin with the guardian role
+/// - 1: instance_pda - pda, writable
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct PauseAdmin {
+    instance_name: String,
+    instance_pda_bump_seed: u8,
+}
+
+impl PauseAdmin {
+    pub fn build_instruction(
+        program_id: &Pubkey,
+        guardian: &Pubkey,
+        instance_name: String,
+    ) -> Result<Instruction> {
+        let (instance_pda, instance_pda_bump_seed) = make_instance_pda(program_id, &instance_name);
+
+        let instr = SlqInstruction::Admin(SlqAdminInstruction::Pause(PauseAdmin {
+            instance_name,
+            instance_pda_bump_seed,
+        }));
+
+        let accounts = vec![
+            AccountMeta::new_readonly(*guardian, true),
+            AccountMeta::new(instance_pda, false),
+        ];
+
+        Ok(Instruction::new_with_borsh(*program_id, &instr, accounts))
+    }
+
+    fn exec(&self, program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+
+        let guardian = next_account_info(accounts_iter)?;
+        let instance_pda = next_account_info(accounts_iter)?;
+
+        {
+            check::signer(guardian, "guardian")?;
+            check::writable(instance_pda, "instance_pda")?;
+            check::owner(instance_pda, program_id, "instance_pda")?;
+
+            verify_pda(
+                program_id,
+                &self.instance_name,
+                instance_pda.key,
+                self.instance_pda_bump_seed,
+                make_instance_pda,
+            )?;
+        }
+
+        let mut instance = SlqInstance::load(&instance_pda.data.borrow())?;
+
+        let is_guardian = instance.guardians.contains(guardian.key);
+        if !is_guardian {
+            verify_admin_role(
+                program_id,
+                &instance.admin_config,
+                guardian.key,
+                Roles::GUARDIAN,
+            )?;
+        }
+
+        msg!("instance paused by guardian {}", guardian.key);
+        instance.paused = true;
+        // A guardian outside the admin set isn't admin activity,
+        // so it can't hold off recovery.
+        if !is_guardian {
+            instance.record_admin_action()?;
+        }
+        instance.store(&mut instance_pda.data.borrow_mut())?;
+
+        Ok(())
+    }
+}
+
+/// Lifts a guardian's pause.
+///
+/// Needs the highest threshold of any action, approved by admin signers directly,
+/// as neither proposals nor invokes can run while paused.
+///
+/// # Accounts
+///
+/// - 0: rent_payer - writable, signer
+/// - 1: instance_pda - pda, writable
+/// - 2..: admin_accounts - signer, distinct admins whose weights meet the unpause threshold
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct UnpauseAdmin {
+    instance_name: String,
+    expected_config_epoch: u64,
+    instance_pda_bump_seed: u8,
+}
+
+impl UnpauseAdmin {
+    pub fn build_instruction(
+        program_id: &Pubkey,
+        rent_payer: &Pubkey,
+        admin_pubkeys: &[Pubkey],
+        instance_name: String,
+        expected_config_epoch: u64,
+    ) -> Result<Instruction> {
+        let (instance_pda, instance_pda_bump_seed) = make_instance_pda(program_id, &instance_name);
+
+        let instr = SlqInstruction::Admin(SlqAdminInstruction::Unpause(UnpauseAdmin {
+            instance_name,
+            expected_config_epoch,
+            instance_pda_bump_seed,
+        }));
+
+        let mut accounts = vec![
+            AccountMeta::new(*rent_payer, true),
+            AccountMeta::new(instance_pda, false),
+        ];
+
+        accounts.extend(
+            admin_pubkeys
+                .iter()
+                .map(|admin_pubkey| AccountMeta::new_readonly(*admin_pubkey, true)),
+        );
+
+        Ok(Instruction::new_with_borsh(*program_id, &instr, accounts))
+    }
+
+    fn exec(&self, program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+
+        let rent_payer = next_account_info(accounts_iter)?;
+        let instance_pda = next_account_info(accounts_iter)?;
+
+        {
+            check::writable(rent_payer, "rent_payer")?;
+            check::signer(rent_payer, "rent_payer")?;
+            check::writable(instance_pda, "instance_pda")?;
+            check::owner(instance_pda, program_id, "instance_pda")?;
+
+            verify_pda(
+                program_id,
+                &self.instance_name,
+                instance_pda.key,
+                self.instance_pda_bump_seed,
+                make_instance_pda,
+            )?;
+        }
+
+        let mut instance = SlqInstance::load(&instance_pda.data.borrow())?;
+        verify_config_epoch(&instance, self.expected_config_epoch)?;
+
+        verify_admin_signers(
+            program_id,
+            &instance.admin_config,
+            ApprovalAction::Unpause,
+            accounts_iter.as_slice(),
+        )?;
+
+        instance.paused = false;
+        instance.record_admin_action()?;
+        instance.store(&mut instance_pda.data.borrow_mut())?;
+
+        Ok(())
+    }
+}
+
+/// Replaces the whole admin config in one step,
+/// so no invalid intermediate config is ever stored.
+/// Any pending nomination is dropped.
+///
+/// Needs the stricter of the admin-set and threshold-change thresholds.
+/// The replaced config is logged as an `AdminConfigReplaced` event.
+/// The instance account is resized to fit the new config,
+/// with `rent_payer` paying or receiving the difference in rent.
+///
+/// # Accounts
+///
+/// - 0: rent_payer - writable, signer
+/// - 1: instance_pda - pda, writable
+/// - 2: system_program - executable
+/// - 3..: admin_accounts - signer, distinct admins whose weights meet the threshold
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct SetAdminConfigAdmin {
+    instance_name: String,
+    expected_config_epoch: u64,
+    admin_config: AdminConfig,
+    instance_pda_bump_seed: u8,
+}
+
+impl SetAdminConfigAdmin {
+    pub fn build_instruction(
+        program_id: &Pubkey,
+        rent_payer: &Pubkey,
+        admin_pubkeys: &[Pubkey],
+        instance_name: String,
+        expected_config_epoch: u64,
+        admin_config: AdminConfig,
+    ) -> Result<Instruction> {
+        let (instance_pda, instance_pda_bump_seed) = make_instance_pda(program_id, &instance_name);
+
+        let instr =
+            SlqInstruction::Admin(SlqAdminInstruction::SetAdminConfig(SetAdminConfigAdmin {
+                instance_name,
+                expected_config_epoch,
+                admin_config,
+                instance_pda_bump_seed,
+            }));
+
+        let mut accounts = vec![
+            AccountMeta::new(*rent_payer, true),
+            AccountMeta::new(instance_pda, false),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ];
+
+        accounts.extend(
+            admin_pubkeys
+                .iter()
+                .map(|admin_pubkey| AccountMeta::new_readonly(*admin_pubkey, true)),
+        );
+
+        Ok(Instruction::new_with_borsh(*program_id, &instr, accounts))
+    }
+
+    fn exec(&self, program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+
+        let rent_payer = next_account_info(accounts_iter)?;
+        let instance_pda = next_account_info(accounts_iter)?;
+        let system_program = next_account_info(accounts_iter)?;
+
+        {
+            check::writable(rent_payer, "rent_payer")?;
+            check::signer(rent_payer, "rent_payer")?;
+            check::writable(instance_pda, "instance_pda")?;
+            check::owner(instance_pda, program_id, "instance_pda")?;
+            check::system_program(system_program)?;
+
+            verify_pda(
+                program_id,
+                &self.instance_name,
+                instance_pda.key,
+                self.instance_pda_bump_seed,
+                make_instance_pda,
+            )?;
+        }
+
+        let mut instance = SlqInstance::load(&instance_pda.data.borrow())?;
+        verify_config_epoch(&instance, self.expected_config_epoch)?;
+
+        let admin_config = &instance.admin_config;
+        verify_admin_approval(
+            program_id,
+            instance_pda.key,
+            admin_config,
+            admin_config.stricter(
+                ApprovalAction::AdminSetChange,
+                ApprovalAction::ThresholdChange,
+            ),
+            accounts_iter.as_slice(),
+        )?;
+
+        validate::admin_config(&self.admin_config)?;
+        let old_admin_config =
+            std::mem::replace(&mut instance.admin_config, self.admin_config.clone());
+        instance.pending_admin = None;
+        instance.bump_config_epoch()?;
+
+        let event = AdminConfigReplaced {
+            instance: *instance_pda.key,
+            old_admin_config,
+            config_epoch: instance.config_epoch,
+        };
+        msg!("replaced admin config: {:?}", event);
+        sol_log_data(&[&borsh::to_vec(&event)?]);
+
+        resize_account(
+            instance_pda,
+            rent_payer,
+            system_program,
+            instance.packed_len()?,
+        )?;
+        instance.record_admin_action()?;
+        instance.store(&mut instance_pda.data.borrow_mut())?;
+
+        Ok(())
+    }
+}
+
+/// Sets or removes the instance's recovery config, dropping any pending recovery.
+///
+/// Needs the stricter of the admin-set and threshold-change thresholds,
+/// as the recovery accounts may eventually replace the admins.
+/// The instance account is resized to fit the new config,
+/// with `rent_payer` paying or receiving the difference in rent.
+///
+/// # Accounts
+///
+/// - 0: rent_payer - writable, signer
+/// - 1: instance_pda - pda, writable
+/// - 2: system_program - executable
+/// - 3..: admin_accounts - signer, distinct admins whose weights meet the threshold
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct SetRecoveryConfigAdmin {
+    instance_name: String,
+    expected_config_epoch: u64,
+    recovery: Option<RecoveryConfig>,
+    instance_pda_bump_seed: u8,
+}
+
+impl SetRecoveryConfigAdmin {
+    pub fn build_instruction(
+        program_id: &Pubkey,
+        rent_payer: &Pubkey,
+        admin_pubkeys: &[Pubkey],
+        instance_name: String,
+        expected_config_epoch: u64,
+        recovery: Option<RecoveryConfig>,
+    ) -> Result<Instruction> {
+        let (instance_pda, instance_pda_bump_seed) = make_instance_pda(program_id, &instance_name);
+
+        let instr = SlqInstruction::Admin(SlqAdminInstruction::SetRecoveryConfig(
+            SetRecoveryConfigAdmin {
+                instance_name,
+                expected_config_epoch,
+                recovery,
+                instance_pda_bump_seed,
+            },
+        ));
+
+        let mut accounts = vec![
+            AccountMeta::new(*rent_payer, true),
+            AccountMeta::new(instance_pda, false),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ];
+
+        accounts.extend(
+            admin_pubkeys
+                .iter()
+                .map(|admin_pubkey| AccountMeta::new_readonly(*admin_pubkey, true)),
+        );
+
+        Ok(Instruction::new_with_borsh(*program_id, &instr, accounts))
+    }
+
+    fn exec(&self, program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+
+        let rent_payer = next_account_info(accounts_iter)?;
+        let instance_pda = next_account_info(accounts_iter)?;
+        let system_program = next_account_info(accounts_iter)?;
+
+        {
+            check::writable(rent_payer, "rent_payer")?;
+            check::signer(rent_payer, "rent_payer")?;
+            check::writable(instance_pda, "instance_pda")?;
+            check::owner(instance_pda, program_id, "instance_pda")?;
+            check::system_program(system_program)?;
+
+            verify_pda(
+                program_id,
+                &self.instance_name,
+                instance_pda.key,
+                self.instance_pda_bump_seed,
+                make_instance_pda,
+            )?;
+        }
+
+        let mut instance = SlqInstance::load(&instance_pda.data.borrow())?;
+        verify_config_epoch(&instance, self.expected_config_epoch)?;
+
+        let admin_config = &instance.admin_config;
+        verify_admin_approval(
+            program_id,
+            instance_pda.key,
+            admin_config,
+            admin_config.stricter(
+                ApprovalAction::AdminSetChange,
+                ApprovalAction::ThresholdChange,
+            ),
+            accounts_iter.as_slice(),
+        )?;
+
+        if let Some(recovery) = &self.recovery {
+            validate::recovery_config(recovery)?;
+        }
+        instance.recovery = self.recovery.clone();
+        instance.pending_recovery = None;
+        instance.bump_config_epoch()?;
+
+        resize_account(
+            instance_pda,
+            rent_payer,
+            system_program,
+            instance.packed_len()?,
+        )?;
+        instance.record_admin_action()?;
+        instance.store(&mut instance_pda.data.borrow_mut())?;
+
+        Ok(())
+    }
+}
+
+/// Replaces the instance's guardians, the accounts outside the admin set that may pause it.
+///
+/// Needs the admin-set threshold, as a guardian can halt withdrawals.
+/// The instance account is resized to fit the new guardians,
+/// with `rent_payer` paying or receiving the difference in rent.
+///
+/// # Accounts
+///
+/// - 0: rent_payer - writable, signer
+/// - 1: instance_pda - pda, writable
+/// - 2: system_program - executable
+/// - 3..: admin_accounts - signer, distinct admins whose weights meet the admin-set threshold
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct SetGuardiansAdmin {
+    instance_name: String,
+    expected_config_epoch: u64,
+    guardians: Vec<Pubkey>,
+    instance_pda_bump_seed: u8,
+}
+
+impl SetGuardiansAdmin {
+    pub fn build_instruction(
+        program_id: &Pubkey,
+        rent_payer: &Pubkey,
+        admin_pubkeys: &[Pubkey],
+        instance_name: String,
+        expected_config_epoch: u64,
+        guardians: Vec<Pubkey>,
+    ) -> Result<Instruction> {
+        let (instance_pda, instance_pda_bump_seed) = make_instance_pda(program_id, &instance_name);
+
+        let instr = SlqInstruction::Admin(SlqAdminInstruction::SetGuardians(SetGuardiansAdmin {
+            instance_name,
+            expected_config_epoch,
+            guardians,
+            instance_pda_bump_seed,
+        }));
+
+        let mut accounts = vec![
+            AccountMeta::new(*rent_payer, true),
+            AccountMeta::new(instance_pda, false),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ];
+
+        accounts.extend(
+            admin_pubkeys
+                .iter()
+                .map(|admin_pubkey| AccountMeta::new_readonly(*admin_pubkey, true)),
+        );
+
+        Ok(Instruction::new_with_borsh(*program_id, &instr, accounts))
+    }
+
+    fn exec(&self, program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+
+        let rent_payer = next_account_info(accounts_iter)?;
+        let instance_pda = next_account_info(accounts_iter)?;
+        let system_program = next_account_info(accounts_iter)?;
+
+        {
+            check::writable(rent_payer, "rent_payer")?;
+            check::signer(rent_payer, "rent_payer")?;
+            check::writable(instance_pda, "instance_pda")?;
+            check::owner(instance_pda, program_id, "instance_pda")?;
+            check::system_program(system_program)?;
+
+            verify_pda(
+                program_id,
+                &self.instance_name,
+                instance_pda.key,
+                self.instance_pda_bump_seed,
+                make_instance_pda,
+            )?;
+        }
+
+        let mut instance = SlqInstance::load(&instance_pda.data.borrow())?;
+        verify_config_epoch(&instance, self.expected_config_epoch)?;
+
+        verify_admin_approval(
+            program_id,
+            instance_pda.key,
+            &instance.admin_config,
+            ApprovalAction::AdminSetChange,
+            accounts_iter.as_slice(),
+        )?;
+
+        validate::guardians(&self.guardians)?;
+        instance.guardians = self.guardians.clone();
+
+        resize_account(
+            instance_pda,
+            rent_payer,
+            system_program,
+            instance.packed_len()?,
+        )?;
+        instance.record_admin_action()?;
+        instance.store(&mut instance_pda.data.borrow_mut())?;
+
+        Ok(())
+    }
+}
+
+/// Logged as program data when an admin config is replaced,
+/// by `SetAdminConfigAdmin` or a completed recovery.
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct AdminConfigReplaced {
+    pub instance: Pubkey,
+    pub old_admin_config: AdminConfig,
+    /// The instance's config epoch after the replacement.
+    pub config_epoch: u64,
+}
+
+/// Verifies that the admin config hasn't changed since the instruction was built.
+///
+/// Admin instructions carry the `config_epoch` of the instance they were
+/// built against, so two instructions built from the same snapshot
+/// can't both apply.
+pub(crate) fn verify_config_epoch(
+    instance: &SlqInstance,
+    expected_config_epoch: u64,
+) -> ProgramResult {
+    if instance.config_epoch != expected_config_epoch {
+        msg!(
+            "expected config epoch {}, instance is at {}",
+            expected_config_epoch,
+            instance.config_epoch
+        );
+        return Err(SlqError::ConfigChanged.into());
+    }
+
+    Ok(())
+}
+
+/// Verifies that the admin signers approve an admin instruction.
+///
+/// `admin_signers` are the accounts trailing the instruction's fixed accounts.
+///
+/// The instance's authority pda approves on its own, as it only signs
+/// once an admin invoke or an executed proposal has met the threshold
+/// of `invoke_action` for this instruction.
+///
+/// Otherwise the instance must have no execution delay,
+/// and the signers must pass `verify_admin_signers`.
+pub fn verify_admin_approval(
+    program_id: &Pubkey,
+    instance_pda: &Pubkey,
+    admin_config: &AdminConfig,
+    action: ApprovalAction,
+    admin_signers: &[AccountInfo],
+) -> ProgramResult {
+    if let [admin_signer] = admin_signers {
+        let (authority_pda, _) = make_authority_pda(program_id, instance_pda);
+        if admin_signer.is_signer && *admin_signer.key == authority_pda {
+            return Ok(());
+        }
+    }
+
+    if admin_config.policy.execution_delay > 0 {
+        msg!(
+            "instance has an execution delay of {} seconds, submit this as a proposal",
+            admin_config.policy.execution_delay
+        );
+        return Err(SlqError::TimelockRequired.into());
+    }
+
+    verify_admin_signers(program_id, admin_config, action, admin_signers)
+}
+
+/// Verifies that each signer is a member of `admin_config.admin_accounts`
+/// holding the approver role, none appears twice,
+/// and their weights sum to at least the threshold for `action`.
+pub fn verify_admin_signers(
+    program_id: &Pubkey,
+    admin_config: &AdminConfig,
+    action: ApprovalAction,
+    admin_signers: &[AccountInfo],
+) -> ProgramResult {
+    let mut approvals: Vec<Pubkey> = Vec::with_capacity(admin_signers.len());
+    let mut approved_weight: u32 = 0;
+
+    for admin_signer in admin_signers {
+        if !admin_signer.is_signer {
+            msg!("admin account {} did not sign", admin_signer.key);
+            return Err(SlqError::MissingSigner.into());
+        }
+        let index = verify_admin_role(program_id, admin_config, admin_signer.key, Roles::APPROVER)?;
+        let weight = admin_config.admin_accounts[index].weight;
+        if approvals.contains(admin_signer.key) {
+            msg!("admin account {} signed more than once", admin_signer.key);
+            return Err(SlqError::DuplicateAdminSigner.into());
+        }
+
+        approvals.push(*admin_signer.key);
+        approved_weight += u32::from(weight);
+    }
+
+    let approval_threshold = admin_config.threshold_for(action);
+    if approved_weight < u32::from(approval_threshold) {
+        msg!(
+            "approved admin weight is {}, approval threshold for {:?} is {}",
+            approved_weight,
+            action,
+            approval_threshold
+        );
+        return Err(SlqError::ApprovalThresholdNotMet.into());
+    }
+
+    Ok(())
+}
+
+/// Verifies that the instance's authority may invoke `instructions`.
+///
+/// While the instance is paused, that is only so if they all change the admin set.
+pub fn verify_invoke_allowed<'a>(
+    program_id: &Pubkey,
+    instance: &SlqInstance,
+    instructions: impl IntoIterator<Item = &'a StoredInstruction>,
+) -> ProgramResult {
+    if instance.paused
+        && instructions
+            .into_iter()
+            .all(|instr| changes_admin_set(program_id, instr))
+    {
+        return Ok(());
+    }
+
+    instance.verify_not_paused()
+}
+
+fn changes_admin_set(program_id: &Pubkey, instr: &StoredInstruction) -> bool {
+    if instr.program_id != *program_id {
+        return false;
+    }
+
+    match SlqInstruction::try_from_slice(&instr.data) {
+        Ok(SlqInstruction::Admin(admin_instr)) => admin_instr.changes_admin_set(),
+        _ => false,
+    }
+}
+
+/// Verifies that `admin` signs for an admin account holding any of `roles`,
+/// returning its index in `admin_config.admin_accounts`.
+///
+/// An admin instance signs with its authority pda.
+pub fn verify_admin_role(
+    program_id: &Pubkey,
+    admin_config: &AdminConfig,
+    admin: &Pubkey,
+    roles: Roles,
+) -> Result<usize, ProgramError> {
+    let index = admin_config
+        .signer_position(program_id, admin)
+        .ok_or_else(|| {
+            msg!("account {} is not an admin account", admin);
+            SlqError::NotAnAdmin
+        })?;
+
+    if !admin_config.admin_accounts[index].has_any_role(roles) {
+        msg!("admin account {} does not hold the {} role", admin, roles);
+        return Err(SlqError::MissingRole.into());
+    }
+
+    Ok(index)
+}
+
+/// The action of invoking `instructions` with the instance's authority.
+///
+/// The authority approves slq admin instructions in place of admin signers,
+/// so those need the threshold of their own action.
+/// Upgrading a program needs the program-upgrade threshold,
+/// and anything else the threshold of `otherwise`.
+/// The strictest of these applies to all of `instructions`.
+pub fn invoke_action<'a>(
+    program_id: &Pubkey,
+    admin_config: &AdminConfig,
+    instructions: impl IntoIterator<Item = &'a StoredInstruction>,
+    otherwise: ApprovalAction,
+) -> ApprovalAction {
+    instructions
+        .into_iter()
+        .map(|instr| instruction_action(program_id, admin_config, instr, otherwise))
+        .fold(otherwise, |strictest, action| {
+            admin_config.stricter(strictest, action)
+        })
+}
+
+fn instruction_action(
+    program_id: &Pubkey,
+    admin_config: &AdminConfig,
+    instr: &StoredInstruction,
+    otherwise: ApprovalAction,
+) -> ApprovalAction {
+    if instr.program_id == bpf_loader_upgradeable::id() {
+        return ApprovalAction::ProgramUpgrade;
+    }
+    if instr.program_id != *program_id {
+        return otherwise;
+    }
+
+    match SlqInstruction::try_from_slice(&instr.data) {
+        Ok(SlqInstruction::Admin(admin_instr)) => {
+            admin_instr.approval_action(program_id, admin_config)
+        }
+        Ok(SlqInstruction::Vault(vault_instr)) => {
+            vault_instr.approval_action(admin_config, otherwise)
+        }
+        _ => otherwise,
+    }
+}
+
+impl SlqAdminInstruction {
+    /// The action this instruction needs approval for.
+    pub fn approval_action(
+        &self,
+        program_id: &Pubkey,
+        admin_config: &AdminConfig,
+    ) -> ApprovalAction {
+        match self {
+            SlqAdminInstruction::ChangeApprovalThreshold(_)
+            | SlqAdminInstruction::SetApprovalPolicy(_) => ApprovalAction::ThresholdChange,
+            SlqAdminInstruction::AddAdminAccount(_)
+            | SlqAdminInstruction::NominateAdminAccount(_)
+            | SlqAdminInstruction::RemoveAdminAccount(_)
+            | SlqAdminInstruction::RotateAdminKey(_)
+            | SlqAdminInstruction::SetGuardians(_) => ApprovalAction::AdminSetChange,
+            SlqAdminInstruction::Invoke(instr) => invoke_action(
+                program_id,
+                admin_config,
+                Some(&instr.instruction),
+                ApprovalAction::Default,
+            ),
+            SlqAdminInstruction::DestroyInstance(_)
+            | SlqAdminInstruction::AcceptAdminAccount(_)
+            | SlqAdminInstruction::Pause(_) => ApprovalAction::Default,
+            SlqAdminInstruction::Unpause(_) => ApprovalAction::Unpause,
+            SlqAdminInstruction::SetAdminConfig(_) | SlqAdminInstruction::SetRecoveryConfig(_) => {
+                admin_config.stricter(
+                    ApprovalAction::AdminSetChange,
+                    ApprovalAction::ThresholdChange,
+                )
+            }
+        }
+    }
+
+    /// Whether this instruction only changes who the admins are,
+    /// which stays allowed while the instance is paused.
+    pub fn changes_admin_set(&self) -> bool {
+        matches!(
+            self,
+            SlqAdminInstruction::AddAdminAccount(_)
+                | SlqAdminInstruction::NominateAdminAccount(_)
+                | SlqAdminInstruction::AcceptAdminAccount(_)
+                | SlqAdminInstruction::RemoveAdminAccount(_)
+                | SlqAdminInstruction::RotateAdminKey(_)
+                | SlqAdminInstruction::SetAdminConfig(_)
+        )
+    }
+}
@@ -0,0 +1,76 @@
+//! Account checks shared by the instruction handlers.
+//!
+//! Each check logs the name of the offending account before failing.
+
+use solana_program::system_program;
+use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult, msg, pubkey::Pubkey};
+
+use crate::error::SlqError;
+
+pub fn signer(account: &AccountInfo, name: &str) -> ProgramResult {
+    if !account.is_signer {
+        msg!("{} must be a signer", name);
+        return Err(SlqError::MissingSigner.into());
+    }
+
+    Ok(())
+}
+
+pub fn writable(account: &AccountInfo, name: &str) -> ProgramResult {
+    if !account.is_writable {
+        msg!("{} must be writable", name);
+        return Err(SlqError::AccountNotWritable.into());
+    }
+
+    Ok(())
+}
+
+pub fn owner(account: &AccountInfo, owner: &Pubkey, name: &str) -> ProgramResult {
+    if account.owner != owner {
+        msg!("{} is owned by {}, expected {}", name, account.owner, owner);
+        return Err(SlqError::WrongAccountOwner.into());
+    }
+
+    Ok(())
+}
+
+pub fn uninitialized(account: &AccountInfo, name: &str) -> ProgramResult {
+    let initialized = {
+        account.owner != &system_program::ID
+            || **account.lamports.borrow() > 0
+            || !account.data.borrow().is_empty()
+    };
+    if initialized {
+        msg!("{} has already been initialized", name);
+        return Err(SlqError::AccountAlreadyInitialized.into());
+    }
+
+    Ok(())
+}
+
+pub fn system_program(account: &AccountInfo) -> ProgramResult {
+    if account.key != &system_program::ID || !account.executable {
+        msg!("unexpected system program {}", account.key);
+        return Err(SlqError::WrongSystemProgram.into());
+    }
+
+    Ok(())
+}
+
+pub fn token_program(account: &AccountInfo) -> ProgramResult {
+    if account.key != &spl_token::id() || !account.executable {
+        msg!("unexpected token program {}", account.key);
+        return Err(SlqError::WrongTokenProgram.into());
+    }
+
+    Ok(())
+}
+
+pub fn associated_token_program(account: &AccountInfo) -> ProgramResult {
+    if account.key != &spl_associated_token_account::id() || !account.executable {
+        msg!("unexpected associated token program {}", account.key);
+        return Err(SlqError::WrongTokenProgram.into());
+    }
+
+    Ok(())
+}
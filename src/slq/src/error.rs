@@ -0,0 +1,137 @@
+use num_derive::FromPrimitive;
+use num_traits::FromPrimitive;
+use solana_program::program_error::ProgramError;
+use std::fmt;
+
+/// Errors returned by the slq program.
+///
+/// Each variant is returned to the runtime as `ProgramError::Custom`
+/// with the variant's discriminant as the error code.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, FromPrimitive)]
+#[repr(u32)]
+pub enum SlqError {
+    WrongPda = 0,
+    WrongBumpSeed,
+    MissingSigner,
+    AccountNotWritable,
+    WrongAccountOwner,
+    AccountAlreadyInitialized,
+    WrongSystemProgram,
+    InsufficientFunds,
+    NotAnAdmin,
+    DuplicateAdminSigner,
+    ApprovalThresholdNotMet,
+    InvalidApprovalThreshold,
+    TooManyAdminAccounts,
+    ArithmeticOverflow,
+    WrongInstance,
+    ProposalAlreadyExecuted,
+    AlreadyApproved,
+    InstanceInUse,
+    WrongAccountType,
+    WrongAccountVersion,
+    InvalidAdminWeight,
+    MissingRole,
+    TimelockRequired,
+    TimelockNotElapsed,
+    StaleProposal,
+    ProposalExpired,
+    ConfigChanged,
+    NoAdminAccounts,
+    DuplicateAdminAccount,
+    InvalidRoles,
+    InvalidExecutionDelay,
+    NotNominated,
+    InstancePaused,
+    InvalidRecoveryConfig,
+    RecoveryNotConfigured,
+    NotARecoveryAccount,
+    AdminsStillActive,
+    RecoveryInProgress,
+    NoPendingRecovery,
+    WrongTokenProgram,
+    WrongTokenAccount,
+    InvalidSpendingLimit,
+    NotAnOwner,
+    DuplicateOwnerSigner,
+    TooManyOwners,
+    WrongAdminKind,
+    InvalidGuardians,
+    WrongProposer,
+    VaultNotEmpty,
+}
+
+impl SlqError {
+    /// Recovers the error from a `ProgramError::Custom` code.
+    pub fn from_code(code: u32) -> Option<SlqError> {
+        SlqError::from_u32(code)
+    }
+}
+
+impl fmt::Display for SlqError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let msg = match self {
+            SlqError::WrongPda => "account is not the expected program derived address",
+            SlqError::WrongBumpSeed => "unexpected program derived address bump seed",
+            SlqError::MissingSigner => "a required account did not sign",
+            SlqError::AccountNotWritable => "a required account is not writable",
+            SlqError::WrongAccountOwner => "account is not owned by the expected program",
+            SlqError::AccountAlreadyInitialized => "account has already been initialized",
+            SlqError::WrongSystemProgram => "unexpected system program account",
+            SlqError::InsufficientFunds => "not enough lamports",
+            SlqError::NotAnAdmin => "signer is not an admin account",
+            SlqError::DuplicateAdminSigner => "admin account signed more than once",
+            SlqError::ApprovalThresholdNotMet => "not enough admin approvals",
+            SlqError::InvalidApprovalThreshold => "invalid approval threshold",
+            SlqError::TooManyAdminAccounts => "too many admin accounts",
+            SlqError::ArithmeticOverflow => "arithmetic overflow",
+            SlqError::WrongInstance => "account belongs to a different instance",
+            SlqError::ProposalAlreadyExecuted => "proposal has already been executed",
+            SlqError::AlreadyApproved => "admin account has already approved",
+            SlqError::InstanceInUse => "instance is still referenced by other accounts",
+            SlqError::WrongAccountType => "account is not of the expected type",
+            SlqError::WrongAccountVersion => "account layout version is not supported",
+            SlqError::InvalidAdminWeight => "admin weight must be greater than 0",
+            SlqError::MissingRole => "admin account does not hold the required role",
+            SlqError::TimelockRequired => "instance has an execution delay, use a proposal",
+            SlqError::TimelockNotElapsed => "proposal execution delay has not elapsed",
+            SlqError::StaleProposal => "proposal was created under an earlier admin config",
+            SlqError::ProposalExpired => "proposal has expired",
+            SlqError::ConfigChanged => "admin config changed since the instruction was built",
+            SlqError::NoAdminAccounts => "instance must have at least 1 admin account",
+            SlqError::DuplicateAdminAccount => "admin account appears more than once",
+            SlqError::InvalidRoles => "admin account has unknown roles",
+            SlqError::InvalidExecutionDelay => "execution delay must not be negative",
+            SlqError::NotNominated => "account has not been nominated as an admin",
+            SlqError::InstancePaused => "instance is paused",
+            SlqError::InvalidRecoveryConfig => "invalid recovery config",
+            SlqError::RecoveryNotConfigured => "instance has no recovery config",
+            SlqError::NotARecoveryAccount => "signer is not a recovery account",
+            SlqError::AdminsStillActive => "admins have acted within the inactivity period",
+            SlqError::RecoveryInProgress => "a recovery is already pending",
+            SlqError::NoPendingRecovery => "instance has no pending recovery",
+            SlqError::WrongTokenProgram => "unexpected token program account",
+            SlqError::WrongTokenAccount => {
+                "token account is not the vault's associated token account"
+            }
+            SlqError::InvalidSpendingLimit => "invalid spending limit",
+            SlqError::NotAnOwner => "signer is not a multisig owner",
+            SlqError::DuplicateOwnerSigner => "multisig owner signed more than once",
+            SlqError::TooManyOwners => "too many multisig owners",
+            SlqError::WrongAdminKind => "admin account is of the wrong kind",
+            SlqError::InvalidGuardians => "invalid guardian set",
+            SlqError::WrongProposer => "account is not the proposal's proposer",
+            SlqError::VaultNotEmpty => "vault token account is not empty",
+        };
+
+        write!(f, "{}", msg)
+    }
+}
+
+impl std::error::Error for SlqError {}
+
+impl From<SlqError> for ProgramError {
+    fn from(err: SlqError) -> ProgramError {
+        ProgramError::Custom(err as u32)
+    }
+}
@@ -2,14 +2,15 @@
 
 use anyhow::{anyhow, bail, Result};
 use borsh::{BorshDeserialize, BorshSerialize};
-use solana_program::borsh::get_instance_packed_len;
+use solana_program::borsh1::get_instance_packed_len;
 use solana_program::instruction::{AccountMeta, Instruction};
 use solana_program::sysvar::Sysvar;
 use solana_program::{
     account_info::{next_account_info, next_account_infos, AccountInfo},
+    clock::Clock,
     entrypoint::ProgramResult,
     msg,
-    program::invoke_signed,
+    program::{invoke, invoke_signed},
     program_error::ProgramError,
     pubkey::Pubkey,
     rent::Rent,
@@ -17,9 +18,16 @@ use solana_program::{
 };
 use std::convert::{TryFrom, TryInto};
 
+use crate::check;
+use crate::error::SlqError;
+use crate::state::AdminAccount;
 use crate::state::AdminConfig;
+use crate::state::ApprovalPolicy;
+use crate::state::Roles;
+use crate::state::SlqAccount;
 use crate::state::SlqInstance;
 use crate::state::MAX_ADMIN_ACCOUNTS;
+use crate::validate;
 use crate::SlqInstruction;
 
 pub fn exec(
@@ -46,7 +54,8 @@ pub enum SlqInitializeInstanceInstruction {
 pub struct Init {
     instance_name: String,
     approval_threshold: u8,
-    admin_accounts: Vec<Pubkey>,
+    max_admin_accounts: u8,
+    admin_accounts: Vec<AdminAccount>,
     instance_pda_bump_seed: u8,
 }
 
@@ -56,13 +65,15 @@ impl Init {
         rent_payer: &Pubkey,
         instance_name: String,
         approval_threshold: u8,
-        admin_accounts: Vec<Pubkey>,
+        max_admin_accounts: u8,
+        admin_accounts: Vec<AdminAccount>,
     ) -> Result<Instruction> {
         let (instance_pda, instance_pda_bump_seed) = make_instance_pda(program_id, &instance_name);
 
         let instr = Init {
             instance_name,
             approval_threshold,
+            max_admin_accounts,
             admin_accounts,
             instance_pda_bump_seed,
         };
@@ -89,24 +100,11 @@ impl Init {
         let system_program = next_account_info(accounts_iter)?;
 
         {
-            assert!(rent_payer.is_writable);
-            assert!(rent_payer.is_signer);
-            assert!(instance_pda.is_writable);
-            let instance_pda_initialized = {
-                instance_pda.owner != system_program.key
-                    || **instance_pda.lamports.borrow() > 0
-                    || instance_pda.data.borrow().len() > 0
-            };
-            if instance_pda_initialized {
-                msg!("instance_pda has already been initialized");
-                return Err(ProgramError::AccountAlreadyInitialized);
-            }
-            assert_eq!(
-                system_program.key,
-                &system_program::ID,
-                "unexpected system program id"
-            );
-            assert!(system_program.executable);
+            check::writable(rent_payer, "rent_payer")?;
+            check::signer(rent_payer, "rent_payer")?;
+            check::writable(instance_pda, "instance_pda")?;
+            check::uninitialized(instance_pda, "instance_pda")?;
+            check::system_program(system_program)?;
 
             verify_pda(
                 program_id,
@@ -114,19 +112,35 @@ impl Init {
                 instance_pda.key,
                 self.instance_pda_bump_seed,
                 make_instance_pda,
-            );
+            )?;
         }
 
-        let instance_size = get_instance_packed_len(&SlqInstance::default())?;
+        let admin_config = self.admin_config();
+        validate::admin_config(&admin_config)?;
+
+        let instance = SlqInstance {
+            admin_config,
+            open_proposals: 0,
+            config_epoch: 0,
+            pending_admin: None,
+            paused: false,
+            guardians: Vec::new(),
+            last_admin_action: Clock::get()?.unix_timestamp,
+            recovery: None,
+            pending_recovery: None,
+            vaults: 0,
+        };
+
+        let instance_size = instance.packed_len()?;
         let rent = Rent::get()?;
         let rent_lamports = rent.minimum_balance(instance_size);
 
         if **rent_payer.lamports.borrow() < rent_lamports {
             msg!("rent_payer does not have the enough lamports to pay instance rent");
-            return Err(ProgramError::InsufficientFunds);
+            return Err(SlqError::InsufficientFunds.into());
         }
 
-        let space = instance_size.try_into().unwrap(); // error handling
+        let space = u64::try_from(instance_size).map_err(|_| SlqError::ArithmeticOverflow)?;
         invoke_signed(
             &system_instruction::create_account(
                 rent_payer.key,
@@ -143,49 +157,26 @@ impl Init {
             ]],
         )?;
 
-        let instance = SlqInstance {
-            admin_config: AdminConfig {
-                approval_threshold: self.approval_threshold,
-                admin_accounts: create_admin_accounts_array(&self.admin_accounts),
-            },
-        };
-
         let instance_pda_data = &mut *instance_pda.data.borrow_mut();
 
-        instance.serialize(instance_pda_data)?;
+        instance.store(instance_pda_data)?;
 
         Ok(())
     }
 
     fn validate(&self) -> Result<()> {
-        if self.approval_threshold == 0 {
-            bail!("approval threshold must be greater than 0");
-        }
-
-        if self.admin_accounts.is_empty() {
-            bail!("must have greater than 0 admin accounts");
-        }
-
-        if self.admin_accounts.len() > MAX_ADMIN_ACCOUNTS {
-            bail!(
-                "number of admin accounts must be not be greater than {}",
-                MAX_ADMIN_ACCOUNTS
-            );
-        }
-
-        if usize::from(self.approval_threshold) > self.admin_accounts.len() {
-            bail!("approval threshold must not be greater than number of admin accounts");
-        }
+        validate::admin_config(&self.admin_config())?;
 
-        let mut sorted_accounts = self.admin_accounts.clone();
-        sorted_accounts.sort_unstable();
-        sorted_accounts.dedup();
+        Ok(())
+    }
 
-        if sorted_accounts.len() != self.admin_accounts.len() {
-            bail!("must not have duplicate admin accounts");
+    fn admin_config(&self) -> AdminConfig {
+        AdminConfig {
+            approval_threshold: self.approval_threshold,
+            max_admin_accounts: self.max_admin_accounts,
+            admin_accounts: self.admin_accounts.clone(),
+            policy: ApprovalPolicy::default(),
         }
-
-        Ok(())
     }
 }
 
@@ -194,25 +185,139 @@ pub fn make_instance_pda(program_id: &Pubkey, instance_name: &str) -> (Pubkey, u
     Pubkey::find_program_address(seeds, program_id)
 }
 
+/// The instance's signing authority.
+///
+/// Unlike `instance_pda` it holds no data, so it can own lamports and act as
+/// the authority over other programs' accounts. slq signs for it with
+/// `invoke_as_authority` once admin approval has been verified.
+pub fn make_authority_pda(program_id: &Pubkey, instance_pda: &Pubkey) -> (Pubkey, u8) {
+    let seeds = &[b"authority", instance_pda.as_ref()];
+    Pubkey::find_program_address(seeds, program_id)
+}
+
+pub fn verify_authority_pda(
+    program_id: &Pubkey,
+    instance_pda: &Pubkey,
+    authority_pda_bump_seed: u8,
+) -> ProgramResult {
+    let (_, expected_bump_seed) = make_authority_pda(program_id, instance_pda);
+    if authority_pda_bump_seed != expected_bump_seed {
+        msg!(
+            "expected authority pda bump seed {}, got {}",
+            expected_bump_seed,
+            authority_pda_bump_seed
+        );
+        return Err(SlqError::WrongBumpSeed.into());
+    }
+
+    Ok(())
+}
+
+/// Invokes `instr` with the instance's authority pda as a signer.
+///
+/// Callers must have verified admin approval first.
+pub fn invoke_as_authority(
+    instr: &Instruction,
+    account_infos: &[AccountInfo],
+    instance_pda: &Pubkey,
+    authority_pda_bump_seed: u8,
+) -> ProgramResult {
+    invoke_signed(
+        instr,
+        account_infos,
+        &[&[
+            b"authority",
+            instance_pda.as_ref(),
+            &[authority_pda_bump_seed],
+        ]],
+    )
+}
+
 pub fn verify_pda(
     program_id: &Pubkey,
     seed: &str,
     pda: &Pubkey,
     pda_bump_seed: u8,
     make_pda_fn: impl Fn(&Pubkey, &str) -> (Pubkey, u8),
-) {
-    // todo: better error messages
+) -> ProgramResult {
     let (expected_pda, expected_pda_bump_seed) = make_pda_fn(program_id, seed);
-    assert_eq!(pda, &expected_pda);
-    assert_eq!(pda_bump_seed, expected_pda_bump_seed);
+    if pda != &expected_pda {
+        msg!(
+            "expected pda {} for seed {:?}, got {}",
+            expected_pda,
+            seed,
+            pda
+        );
+        return Err(SlqError::WrongPda.into());
+    }
+    if pda_bump_seed != expected_pda_bump_seed {
+        msg!(
+            "expected pda bump seed {}, got {}",
+            expected_pda_bump_seed,
+            pda_bump_seed
+        );
+        return Err(SlqError::WrongBumpSeed.into());
+    }
+
+    Ok(())
+}
+
+/// Closes a program-owned account, moving its lamports to `recipient`.
+///
+/// The data is zeroed so the account can't be read as initialized
+/// for the rest of the transaction.
+pub fn close_account(account: &AccountInfo, recipient: &AccountInfo) -> ProgramResult {
+    let lamports = account.lamports();
+    let recipient_lamports = recipient
+        .lamports()
+        .checked_add(lamports)
+        .ok_or(SlqError::ArithmeticOverflow)?;
+
+    **recipient.lamports.borrow_mut() = recipient_lamports;
+    **account.lamports.borrow_mut() = 0;
+    account.data.borrow_mut().fill(0);
+
+    Ok(())
 }
 
-pub fn create_admin_accounts_array(accounts: &[Pubkey]) -> [Pubkey; MAX_ADMIN_ACCOUNTS] {
-    let mut array = [Pubkey::default(); MAX_ADMIN_ACCOUNTS];
-    assert!(accounts.len() <= array.len());
-    for (i, account) in accounts.iter().enumerate() {
-        array[i] = *account;
+/// Resizes a program-owned account, keeping it rent exempt.
+///
+/// Growing tops up rent from `rent_payer`,
+/// shrinking refunds the rent no longer needed to `rent_payer`.
+pub fn resize_account<'a>(
+    account: &AccountInfo<'a>,
+    rent_payer: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    new_len: usize,
+) -> ProgramResult {
+    let old_len = account.data_len();
+    let rent = Rent::get()?;
+    let rent_lamports = rent.minimum_balance(new_len);
+    let lamports = account.lamports();
+
+    if new_len > old_len && lamports < rent_lamports {
+        let top_up = rent_lamports - lamports;
+        if rent_payer.lamports() < top_up {
+            msg!("rent_payer does not have the enough lamports to pay account rent");
+            return Err(SlqError::InsufficientFunds.into());
+        }
+
+        invoke(
+            &system_instruction::transfer(rent_payer.key, account.key, top_up),
+            &[rent_payer.clone(), account.clone(), system_program.clone()],
+        )?;
+    } else if new_len < old_len {
+        let refund = lamports
+            .saturating_sub(rent_lamports)
+            .min(rent.minimum_balance(old_len).saturating_sub(rent_lamports));
+        let rent_payer_lamports = rent_payer
+            .lamports()
+            .checked_add(refund)
+            .ok_or(SlqError::ArithmeticOverflow)?;
+
+        **account.lamports.borrow_mut() = lamports - refund;
+        **rent_payer.lamports.borrow_mut() = rent_payer_lamports;
     }
 
-    array
+    account.realloc(new_len, true)
 }
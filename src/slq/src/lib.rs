@@ -15,10 +15,16 @@ use solana_program::{
 use std::convert::{TryFrom, TryInto};
 
 pub mod admin;
+pub mod check;
+pub mod error;
 pub mod init;
+pub mod migrate;
 pub mod multisig;
 pub mod nonce;
+pub mod proposal;
+pub mod recovery;
 pub mod state;
+pub mod validate;
 pub mod vault;
 
 #[cfg(not(feature = "no-entrypoint"))]
@@ -28,7 +34,7 @@ mod entrypoint {
     entrypoint!(process_instruction);
 }
 
-fn process_instruction(
+pub fn process_instruction(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
     instruction_data: &[u8],
@@ -44,6 +50,9 @@ fn process_instruction(
         SlqInstruction::Admin(instr) => admin::exec(program_id, accounts, instr),
         SlqInstruction::Multisig(instr) => multisig::exec(program_id, accounts, instr),
         SlqInstruction::Vault(instr) => vault::exec(program_id, accounts, instr),
+        SlqInstruction::Proposal(instr) => proposal::exec(program_id, accounts, instr),
+        SlqInstruction::Migrate(instr) => migrate::exec(program_id, accounts, instr),
+        SlqInstruction::Recovery(instr) => recovery::exec(program_id, accounts, instr),
     }
 }
 
@@ -54,4 +63,7 @@ pub enum SlqInstruction {
     Admin(admin::SlqAdminInstruction),
     Multisig(multisig::SlqMultisigInstruction),
     Vault(vault::SlqVaultInstruction),
+    Proposal(proposal::SlqProposalInstruction),
+    Migrate(migrate::SlqMigrateInstruction),
+    Recovery(recovery::SlqRecoveryInstruction),
 }
@@ -0,0 +1,347 @@
+#![allow(unused)]
+
+use anyhow::{anyhow, bail, Result};
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::instruction::{AccountMeta, Instruction};
+use solana_program::{
+    account_info::{next_account_info, next_account_infos, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    system_program,
+};
+use std::convert::{TryFrom, TryInto};
+
+use crate::check;
+use crate::error::SlqError;
+use crate::init::{self, resize_account};
+use crate::multisig;
+use crate::state::{
+    read_header, AccountHeader, AccountType, AdminAccount, AdminConfig, AdminKind, ApprovalPolicy,
+    MultisigConfigInstance, Proposal, Roles, SlqAccount, SlqInstance, Vault, ACCOUNT_HEADER_LEN,
+    MAX_ADMIN_ACCOUNTS,
+};
+use crate::validate;
+use crate::SlqInstruction;
+
+pub fn exec(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instr: SlqMigrateInstruction,
+) -> ProgramResult {
+    match instr {
+        SlqMigrateInstruction::MigrateAccount(instr) => instr.exec(program_id, accounts),
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub enum SlqMigrateInstruction {
+    MigrateAccount(MigrateAccount),
+}
+
+/// Upgrades an account in place to the current layout of its type.
+///
+/// Migration only adds the header and new fields with their initial values,
+/// so it doesn't require admin approval.
+/// Accounts that are already current are left unchanged.
+///
+/// Baseline instances and multisig configs are only rewritten
+/// once `account` is verified to be the pda derived from `account_name`,
+/// which other account types ignore.
+///
+/// # Accounts
+///
+/// - 0: rent_payer - writable, signer
+/// - 1: account - writable, owner=program_id
+/// - 2: system_program - executable
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct MigrateAccount {
+    account_type: AccountType,
+    account_name: String,
+}
+
+impl MigrateAccount {
+    pub fn build_instruction(
+        program_id: &Pubkey,
+        rent_payer: &Pubkey,
+        account: &Pubkey,
+        account_type: AccountType,
+        account_name: &str,
+    ) -> Result<Instruction> {
+        let instr =
+            SlqInstruction::Migrate(SlqMigrateInstruction::MigrateAccount(MigrateAccount {
+                account_type,
+                account_name: account_name.to_string(),
+            }));
+
+        let accounts = vec![
+            AccountMeta::new(*rent_payer, true),
+            AccountMeta::new(*account, false),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ];
+
+        Ok(Instruction::new_with_borsh(*program_id, &instr, accounts))
+    }
+
+    fn exec(&self, program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+
+        let rent_payer = next_account_info(accounts_iter)?;
+        let account = next_account_info(accounts_iter)?;
+        let system_program = next_account_info(accounts_iter)?;
+
+        {
+            check::writable(rent_payer, "rent_payer")?;
+            check::signer(rent_payer, "rent_payer")?;
+            check::writable(account, "account")?;
+            check::owner(account, program_id, "account")?;
+            check::system_program(system_program)?;
+        }
+
+        let data = account.data.borrow().to_vec();
+
+        match self.account_type {
+            AccountType::Instance => {
+                let version = stored_version::<SlqInstance>(&data, Some(v0::SLQ_INSTANCE_LEN))?;
+                if version == SlqInstance::VERSION {
+                    return already_current();
+                }
+
+                if version != 0 {
+                    return Err(unsupported(version));
+                }
+
+                verify_v0_pda(
+                    program_id,
+                    account,
+                    &self.account_name,
+                    init::make_instance_pda,
+                )?;
+                let instance = SlqInstance::try_from(v0::SlqInstance::try_from_slice(&data)?)?;
+                store_migrated(account, rent_payer, system_program, &instance)
+            }
+            AccountType::MultisigConfig => {
+                let version = stored_version::<MultisigConfigInstance>(
+                    &data,
+                    Some(v0::MULTISIG_CONFIG_INSTANCE_LEN),
+                )?;
+                if version == MultisigConfigInstance::VERSION {
+                    return already_current();
+                }
+
+                if version != 0 {
+                    return Err(unsupported(version));
+                }
+
+                verify_v0_pda(
+                    program_id,
+                    account,
+                    &self.account_name,
+                    multisig::make_instance_pda,
+                )?;
+                let instance = MultisigConfigInstance::from(
+                    v0::MultisigConfigInstance::try_from_slice(&data)?,
+                );
+                store_migrated(account, rent_payer, system_program, &instance)
+            }
+            // Proposals and vaults only exist in their current layout.
+            AccountType::Proposal => {
+                let version = stored_version::<Proposal>(&data, None)?;
+                if version == Proposal::VERSION {
+                    return already_current();
+                }
+
+                Err(unsupported(version))
+            }
+            AccountType::Vault => {
+                let version = stored_version::<Vault>(&data, None)?;
+                if version == Vault::VERSION {
+                    return already_current();
+                }
+
+                Err(unsupported(version))
+            }
+        }
+    }
+}
+
+/// Reads the layout version of a `T` account.
+///
+/// Version 0 accounts predate `AccountHeader` and are recognized by their fixed size,
+/// but only once the account fails to load as a current account of any type,
+/// so a current account that happens to have that size is never misread.
+fn stored_version<T: SlqAccount>(data: &[u8], v0_len: Option<usize>) -> Result<u8, ProgramError> {
+    let current_type = [
+        loads_as_current::<SlqInstance>(data),
+        loads_as_current::<MultisigConfigInstance>(data),
+        loads_as_current::<Proposal>(data),
+        loads_as_current::<Vault>(data),
+    ]
+    .iter()
+    .flatten()
+    .next()
+    .copied();
+    if current_type == Some(T::ACCOUNT_TYPE) {
+        return Ok(T::VERSION);
+    }
+    if current_type.is_none() && Some(data.len()) == v0_len {
+        return Ok(0);
+    }
+
+    let header = read_header(data)?;
+    if header.account_type != T::ACCOUNT_TYPE {
+        msg!(
+            "expected {:?} account, got {:?}",
+            T::ACCOUNT_TYPE,
+            header.account_type
+        );
+        return Err(SlqError::WrongAccountType.into());
+    }
+
+    Ok(header.version)
+}
+
+/// `T::ACCOUNT_TYPE` if `data` loads as a current `T`.
+fn loads_as_current<T: SlqAccount>(data: &[u8]) -> Option<AccountType> {
+    let current_header = AccountHeader {
+        account_type: T::ACCOUNT_TYPE,
+        version: T::VERSION,
+    };
+    let is_current = data.len() >= ACCOUNT_HEADER_LEN
+        && AccountHeader::try_from_slice(&data[..ACCOUNT_HEADER_LEN]).ok() == Some(current_header)
+        && T::deserialize(&mut &data[ACCOUNT_HEADER_LEN..]).is_ok();
+
+    is_current.then_some(T::ACCOUNT_TYPE)
+}
+
+/// Verifies that the baseline `account` about to be rewritten is the pda of `account_name`.
+fn verify_v0_pda(
+    program_id: &Pubkey,
+    account: &AccountInfo,
+    account_name: &str,
+    make_pda_fn: impl Fn(&Pubkey, &str) -> (Pubkey, u8),
+) -> ProgramResult {
+    let (expected_pda, _) = make_pda_fn(program_id, account_name);
+    if *account.key != expected_pda {
+        msg!(
+            "expected pda {} for name {:?}, got {}",
+            expected_pda,
+            account_name,
+            account.key
+        );
+        return Err(SlqError::WrongPda.into());
+    }
+
+    Ok(())
+}
+
+fn store_migrated<'a, T: SlqAccount>(
+    account: &AccountInfo<'a>,
+    rent_payer: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    value: &T,
+) -> ProgramResult {
+    resize_account(account, rent_payer, system_program, value.packed_len()?)?;
+    value.store(&mut account.data.borrow_mut())
+}
+
+fn already_current() -> ProgramResult {
+    msg!("account is already at the current version");
+    Ok(())
+}
+
+fn unsupported(version: u8) -> ProgramError {
+    msg!("no migration from account version {}", version);
+    SlqError::WrongAccountVersion.into()
+}
+
+/// Layouts from before accounts had an `AccountHeader`.
+mod v0 {
+    use borsh::BorshDeserialize;
+    use solana_program::pubkey::Pubkey;
+
+    pub const SLQ_INSTANCE_LEN: usize = 1 + 32 * 6;
+    pub const MULTISIG_CONFIG_INSTANCE_LEN: usize = 1 + 32 * 5;
+
+    #[derive(BorshDeserialize)]
+    pub struct SlqInstance {
+        pub approval_threshold: u8,
+        pub admin_accounts: [Pubkey; 6],
+    }
+
+    #[derive(BorshDeserialize)]
+    pub struct MultisigConfigInstance {
+        pub approval_threshold: u8,
+        pub owners: [Pubkey; 5],
+    }
+}
+
+/// Migrated instances start from the baseline admin set:
+///
+/// - admins are keys of weight 1 holding every role,
+///   so the approval threshold keeps counting admins
+///   and every admin can still do anything it could before;
+/// - the policy is empty, so every action keeps needing `approval_threshold`,
+///   and there is no execution delay;
+/// - the instance may grow to `MAX_ADMIN_ACCOUNTS` admins;
+/// - the config epoch starts at 0, with no pending admin, pause, guardians,
+///   recovery or vaults.
+///
+/// The last admin action is unknown,
+/// which is harmless as configuring recovery records one.
+///
+/// Baseline instances weren't validated, so one whose admin set
+/// is no longer valid is refused rather than stored.
+impl TryFrom<v0::SlqInstance> for SlqInstance {
+    type Error = SlqError;
+
+    fn try_from(instance: v0::SlqInstance) -> Result<SlqInstance, SlqError> {
+        let instance = SlqInstance {
+            admin_config: AdminConfig {
+                approval_threshold: instance.approval_threshold,
+                max_admin_accounts: MAX_ADMIN_ACCOUNTS as u8,
+                admin_accounts: without_padding(&instance.admin_accounts)
+                    .into_iter()
+                    .map(|pubkey| AdminAccount {
+                        pubkey,
+                        weight: 1,
+                        roles: Roles::ALL,
+                        kind: AdminKind::Key,
+                    })
+                    .collect(),
+                policy: ApprovalPolicy::default(),
+            },
+            open_proposals: 0,
+            config_epoch: 0,
+            pending_admin: None,
+            paused: false,
+            guardians: Vec::new(),
+            last_admin_action: 0,
+            recovery: None,
+            pending_recovery: None,
+            vaults: 0,
+        };
+        validate::admin_config(&instance.admin_config)?;
+
+        Ok(instance)
+    }
+}
+
+impl From<v0::MultisigConfigInstance> for MultisigConfigInstance {
+    fn from(instance: v0::MultisigConfigInstance) -> MultisigConfigInstance {
+        MultisigConfigInstance {
+            approval_threshold: instance.approval_threshold,
+            owners: without_padding(&instance.owners),
+        }
+    }
+}
+
+/// The baseline layouts padded their fixed-size arrays with `Pubkey::default()`.
+fn without_padding(accounts: &[Pubkey]) -> Vec<Pubkey> {
+    accounts
+        .iter()
+        .filter(|account| **account != Pubkey::default())
+        .copied()
+        .collect()
+}
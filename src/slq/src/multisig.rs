@@ -2,7 +2,7 @@
 
 use anyhow::{anyhow, bail, Result};
 use borsh::{BorshDeserialize, BorshSerialize};
-use solana_program::borsh::get_instance_packed_len;
+use solana_program::borsh1::get_instance_packed_len;
 use solana_program::instruction::{AccountMeta, Instruction};
 use solana_program::sysvar::Sysvar;
 use solana_program::{
@@ -17,10 +17,13 @@ use solana_program::{
 };
 use std::convert::{TryFrom, TryInto};
 
-use crate::state::MultisigConfigInstance;
+use crate::check;
+use crate::error::SlqError;
+use crate::init::close_account;
+use crate::state::{MultisigConfigInstance, SlqAccount};
 use crate::SlqInstruction;
 
-pub const MAX_MULTISIG_OWNERS: usize = 5;
+pub const MAX_MULTISIG_OWNERS: usize = crate::state::MAX_ADMIN_ACCOUNTS;
 
 pub fn exec(
     program_id: &Pubkey,
@@ -29,12 +32,14 @@ pub fn exec(
 ) -> ProgramResult {
     match instr {
         SlqMultisigInstruction::Init(instr) => instr.exec(program_id, accounts),
+        SlqMultisigInstruction::Destroy(instr) => instr.exec(program_id, accounts),
     }
 }
 
 #[derive(BorshSerialize, BorshDeserialize, Debug)]
 pub enum SlqMultisigInstruction {
     Init(Init),
+    Destroy(Destroy),
 }
 
 /// # Accounts
@@ -88,24 +93,11 @@ impl Init {
         let system_program = next_account_info(accounts_iter)?;
 
         {
-            assert!(rent_payer.is_writable);
-            assert!(rent_payer.is_signer);
-            assert!(instance_pda.is_writable);
-            let instance_pda_initialized = {
-                instance_pda.owner != system_program.key
-                    || **instance_pda.lamports.borrow() > 0
-                    || instance_pda.data.borrow().len() > 0
-            };
-            if instance_pda_initialized {
-                msg!("instance_pda has already been initialized");
-                return Err(ProgramError::AccountAlreadyInitialized);
-            }
-            assert_eq!(
-                system_program.key,
-                &system_program::ID,
-                "unexpected system program id"
-            );
-            assert!(system_program.executable);
+            check::writable(rent_payer, "rent_payer")?;
+            check::signer(rent_payer, "rent_payer")?;
+            check::writable(instance_pda, "instance_pda")?;
+            check::uninitialized(instance_pda, "instance_pda")?;
+            check::system_program(system_program)?;
 
             verify_pda(
                 program_id,
@@ -113,19 +105,33 @@ impl Init {
                 instance_pda.key,
                 self.instance_pda_bump_seed,
                 make_instance_pda,
+            )?;
+        }
+
+        if self.owners.len() > MAX_MULTISIG_OWNERS {
+            msg!(
+                "at most {} owners allowed, got {}",
+                MAX_MULTISIG_OWNERS,
+                self.owners.len()
             );
+            return Err(SlqError::TooManyOwners.into());
         }
 
-        let instance_size = get_instance_packed_len(&MultisigConfigInstance::default())?;
+        let instance = MultisigConfigInstance {
+            approval_threshold: self.approval_threshold,
+            owners: self.owners.clone(),
+        };
+
+        let instance_size = instance.packed_len()?;
         let rent = Rent::get()?;
         let rent_lamports = rent.minimum_balance(instance_size);
 
         if **rent_payer.lamports.borrow() < rent_lamports {
             msg!("rent_payer does not have the enough lamports to pay instance rent");
-            return Err(ProgramError::InsufficientFunds);
+            return Err(SlqError::InsufficientFunds.into());
         }
 
-        let space = instance_size.try_into().unwrap(); // error handling
+        let space = u64::try_from(instance_size).map_err(|_| SlqError::ArithmeticOverflow)?;
         invoke_signed(
             &system_instruction::create_account(
                 rent_payer.key,
@@ -142,14 +148,9 @@ impl Init {
             ]],
         )?;
 
-        let instance = MultisigConfigInstance {
-            approval_threshold: self.approval_threshold,
-            owners: create_owners_array(&self.owners),
-        };
-
         let instance_pda_data = &mut *instance_pda.data.borrow_mut();
 
-        instance.serialize(instance_pda_data)?;
+        instance.store(instance_pda_data)?;
 
         Ok(())
     }
@@ -186,6 +187,81 @@ impl Init {
     }
 }
 
+/// Closes the multisig instance, returning its rent to `recipient`.
+///
+/// # Accounts
+///
+/// - 0: rent_payer - writable, signer
+/// - 1: instance_pda - pda, writable
+/// - 2: recipient - writable
+/// - 3..: owners - signer, at least `approval_threshold` distinct owners
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct Destroy {
+    instance_name: String,
+    instance_pda_bump_seed: u8,
+}
+
+impl Destroy {
+    pub fn build_instruction(
+        program_id: &Pubkey,
+        rent_payer: &Pubkey,
+        owner_pubkeys: &[Pubkey],
+        instance_name: String,
+        recipient: &Pubkey,
+    ) -> Result<Instruction> {
+        let (instance_pda, instance_pda_bump_seed) = make_instance_pda(program_id, &instance_name);
+
+        let instr = SlqInstruction::Multisig(SlqMultisigInstruction::Destroy(Destroy {
+            instance_name,
+            instance_pda_bump_seed,
+        }));
+
+        let mut accounts = vec![
+            AccountMeta::new(*rent_payer, true),
+            AccountMeta::new(instance_pda, false),
+            AccountMeta::new(*recipient, false),
+        ];
+
+        accounts.extend(
+            owner_pubkeys
+                .iter()
+                .map(|owner_pubkey| AccountMeta::new_readonly(*owner_pubkey, true)),
+        );
+
+        Ok(Instruction::new_with_borsh(*program_id, &instr, accounts))
+    }
+
+    fn exec(&self, program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+
+        let rent_payer = next_account_info(accounts_iter)?;
+        let instance_pda = next_account_info(accounts_iter)?;
+        let recipient = next_account_info(accounts_iter)?;
+
+        {
+            check::writable(rent_payer, "rent_payer")?;
+            check::signer(rent_payer, "rent_payer")?;
+            check::writable(instance_pda, "instance_pda")?;
+            check::owner(instance_pda, program_id, "instance_pda")?;
+            check::writable(recipient, "recipient")?;
+
+            verify_pda(
+                program_id,
+                &self.instance_name,
+                instance_pda.key,
+                self.instance_pda_bump_seed,
+                make_instance_pda,
+            )?;
+        }
+
+        let instance = MultisigConfigInstance::load(&instance_pda.data.borrow())?;
+
+        verify_owner_approval(&instance, accounts_iter.as_slice())?;
+
+        close_account(instance_pda, recipient)
+    }
+}
+
 pub fn make_instance_pda(program_id: &Pubkey, instance_name: &str) -> (Pubkey, u8) {
     let seeds = &[b"multisig-instance", instance_name.as_bytes()];
     Pubkey::find_program_address(seeds, program_id)
@@ -197,19 +273,61 @@ pub fn verify_pda(
     pda: &Pubkey,
     pda_bump_seed: u8,
     make_pda_fn: impl Fn(&Pubkey, &str) -> (Pubkey, u8),
-) {
-    // todo: better error messages
+) -> ProgramResult {
     let (expected_pda, expected_pda_bump_seed) = make_pda_fn(program_id, seed);
-    assert_eq!(pda, &expected_pda);
-    assert_eq!(pda_bump_seed, expected_pda_bump_seed);
+    if pda != &expected_pda {
+        msg!(
+            "expected pda {} for seed {:?}, got {}",
+            expected_pda,
+            seed,
+            pda
+        );
+        return Err(SlqError::WrongPda.into());
+    }
+    if pda_bump_seed != expected_pda_bump_seed {
+        msg!(
+            "expected pda bump seed {}, got {}",
+            expected_pda_bump_seed,
+            pda_bump_seed
+        );
+        return Err(SlqError::WrongBumpSeed.into());
+    }
+
+    Ok(())
 }
 
-pub fn create_owners_array(accounts: &[Pubkey]) -> [Pubkey; MAX_MULTISIG_OWNERS] {
-    let mut array = [Pubkey::default(); MAX_MULTISIG_OWNERS];
-    assert!(accounts.len() <= array.len());
-    for (i, account) in accounts.iter().enumerate() {
-        array[i] = *account;
+/// Verifies that at least `approval_threshold` distinct owners signed.
+pub fn verify_owner_approval(
+    instance: &MultisigConfigInstance,
+    owner_signers: &[AccountInfo],
+) -> ProgramResult {
+    let mut approvals: Vec<Pubkey> = Vec::with_capacity(owner_signers.len());
+
+    for owner_signer in owner_signers {
+        if !owner_signer.is_signer {
+            msg!("owner {} did not sign", owner_signer.key);
+            return Err(SlqError::MissingSigner.into());
+        }
+        if !instance.owners.contains(owner_signer.key) {
+            msg!("account {} is not an owner", owner_signer.key);
+            return Err(SlqError::NotAnOwner.into());
+        }
+        if approvals.contains(owner_signer.key) {
+            msg!("owner {} signed more than once", owner_signer.key);
+            return Err(SlqError::DuplicateOwnerSigner.into());
+        }
+
+        approvals.push(*owner_signer.key);
+    }
+
+    if approvals.len() < usize::from(instance.approval_threshold) {
+        msg!(
+            "{} owner approvals, approval threshold is {}",
+            approvals.len(),
+            instance.approval_threshold
+        );
+        return Err(SlqError::ApprovalThresholdNotMet.into());
     }
 
-    array
+    Ok(())
 }
@@ -1,9 +1,10 @@
 #![allow(unused)]
 
+use crate::check;
 use crate::SlqInstruction;
 use anyhow::{anyhow, bail, Result};
 use borsh::{BorshDeserialize, BorshSerialize};
-use solana_program::borsh::get_instance_packed_len;
+use solana_program::borsh1::get_instance_packed_len;
 use solana_program::instruction::{AccountMeta, Instruction};
 use solana_program::nonce::State;
 use solana_program::system_instruction::assign;
@@ -74,21 +75,13 @@ impl WithdrawNonceAccount {
         let system_program = next_account_info(accounts_iter)?;
 
         {
-            assert!(rent_payer.is_writable);
-            assert!(rent_payer.is_signer);
-            assert!(nonce_account.is_writable);
+            check::writable(rent_payer, "rent_payer")?;
+            check::signer(rent_payer, "rent_payer")?;
+            check::writable(nonce_account, "nonce_account")?;
 
             // todo if nonce account is initialized
-            assert_eq!(
-                nonce_account.owner, system_program.key,
-                "nonce_account isn't owned by system program"
-            );
-            assert_eq!(
-                system_program.key,
-                &system_program::ID,
-                "unexpected system program id"
-            );
-            assert!(system_program.executable);
+            check::owner(nonce_account, &system_program::ID, "nonce_account")?;
+            check::system_program(system_program)?;
         }
         // todo: authorize_nonce_account
 
@@ -111,7 +104,7 @@ impl WithdrawNonceAccount {
                 rent_payer.key,
                 rent_lamports,
             ),
-            &[rent_payer.clone()],
+            std::slice::from_ref(rent_payer),
         )?;
 
         Ok(())
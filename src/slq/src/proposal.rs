@@ -0,0 +1,659 @@
+#![allow(unused)]
+
+use anyhow::{anyhow, bail, Result};
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::borsh1::get_instance_packed_len;
+use solana_program::instruction::{AccountMeta, Instruction};
+use solana_program::sysvar::Sysvar;
+use solana_program::{
+    account_info::{next_account_info, next_account_infos, AccountInfo},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    msg,
+    program::invoke_signed,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    rent::Rent,
+    system_instruction, system_program,
+};
+use std::convert::{TryFrom, TryInto};
+
+use crate::admin::{invoke_action, verify_admin_role, verify_invoke_allowed};
+use crate::check;
+use crate::error::SlqError;
+use crate::init::{
+    close_account, invoke_as_authority, make_authority_pda, make_instance_pda,
+    verify_authority_pda, verify_pda,
+};
+use crate::state::{
+    ApprovalAction, Expiry, Proposal, Roles, SlqAccount, SlqInstance, StoredInstruction,
+};
+use crate::SlqInstruction;
+
+pub fn exec(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instr: SlqProposalInstruction,
+) -> ProgramResult {
+    match instr {
+        SlqProposalInstruction::CreateProposal(instr) => instr.exec(program_id, accounts),
+        SlqProposalInstruction::Approve(instr) => instr.exec(program_id, accounts),
+        SlqProposalInstruction::Execute(instr) => instr.exec(program_id, accounts),
+        SlqProposalInstruction::Cancel(instr) => instr.exec(program_id, accounts),
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub enum SlqProposalInstruction {
+    CreateProposal(CreateProposal),
+    Approve(Approve),
+    Execute(Execute),
+    Cancel(Cancel),
+}
+
+/// # Accounts
+///
+/// - 0: rent_payer - writable, signer
+/// - 1: proposer - signer, admin with the proposer role
+/// - 2: instance_pda - pda, writable
+/// - 3: proposal_pda - pda, writable, uninitialized
+/// - 4: system_program - executable
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct CreateProposal {
+    instance_name: String,
+    proposal_name: String,
+    instructions: Vec<StoredInstruction>,
+    expiry: Option<Expiry>,
+    instance_pda_bump_seed: u8,
+    proposal_pda_bump_seed: u8,
+}
+
+impl CreateProposal {
+    pub fn build_instruction(
+        program_id: &Pubkey,
+        rent_payer: &Pubkey,
+        proposer: &Pubkey,
+        instance_name: String,
+        proposal_name: String,
+        instructions: &[Instruction],
+        expiry: Option<Expiry>,
+    ) -> Result<Instruction> {
+        let (instance_pda, instance_pda_bump_seed) = make_instance_pda(program_id, &instance_name);
+        let (proposal_pda, proposal_pda_bump_seed) =
+            make_proposal_pda(program_id, &instance_pda, &proposal_name);
+
+        if instructions.is_empty() {
+            bail!("a proposal must contain at least one instruction");
+        }
+
+        let instr =
+            SlqInstruction::Proposal(SlqProposalInstruction::CreateProposal(CreateProposal {
+                instance_name,
+                proposal_name,
+                instructions: instructions.iter().map(StoredInstruction::from).collect(),
+                expiry,
+                instance_pda_bump_seed,
+                proposal_pda_bump_seed,
+            }));
+
+        let accounts = vec![
+            AccountMeta::new(*rent_payer, true),
+            AccountMeta::new_readonly(*proposer, true),
+            AccountMeta::new(instance_pda, false),
+            AccountMeta::new(proposal_pda, false),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ];
+
+        Ok(Instruction::new_with_borsh(*program_id, &instr, accounts))
+    }
+
+    fn exec(&self, program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+
+        let rent_payer = next_account_info(accounts_iter)?;
+        let proposer = next_account_info(accounts_iter)?;
+        let instance_pda = next_account_info(accounts_iter)?;
+        let proposal_pda = next_account_info(accounts_iter)?;
+        let system_program = next_account_info(accounts_iter)?;
+
+        {
+            check::writable(rent_payer, "rent_payer")?;
+            check::signer(rent_payer, "rent_payer")?;
+            check::signer(proposer, "proposer")?;
+            check::writable(instance_pda, "instance_pda")?;
+            check::owner(instance_pda, program_id, "instance_pda")?;
+            check::writable(proposal_pda, "proposal_pda")?;
+            check::uninitialized(proposal_pda, "proposal_pda")?;
+            check::system_program(system_program)?;
+
+            verify_pda(
+                program_id,
+                &self.instance_name,
+                instance_pda.key,
+                self.instance_pda_bump_seed,
+                make_instance_pda,
+            )?;
+            verify_pda(
+                program_id,
+                &self.proposal_name,
+                proposal_pda.key,
+                self.proposal_pda_bump_seed,
+                |program_id, proposal_name| {
+                    make_proposal_pda(program_id, instance_pda.key, proposal_name)
+                },
+            )?;
+        }
+
+        let mut instance = SlqInstance::load(&instance_pda.data.borrow())?;
+
+        verify_admin_role(
+            program_id,
+            &instance.admin_config,
+            proposer.key,
+            Roles::PROPOSER,
+        )?;
+
+        let proposal = Proposal {
+            instance: *instance_pda.key,
+            proposer: *proposer.key,
+            instructions: self.instructions.clone(),
+            approvals: 0,
+            executed: false,
+            approved_at: None,
+            config_epoch: instance.config_epoch,
+            expiry: self.expiry,
+        };
+
+        // Room for `approved_at`, which `Approve` sets once the threshold is met.
+        let proposal_size = proposal.packed_len()? + std::mem::size_of::<i64>();
+        let rent = Rent::get()?;
+        let rent_lamports = rent.minimum_balance(proposal_size);
+
+        if **rent_payer.lamports.borrow() < rent_lamports {
+            msg!("rent_payer does not have the enough lamports to pay proposal rent");
+            return Err(SlqError::InsufficientFunds.into());
+        }
+
+        let space = u64::try_from(proposal_size).map_err(|_| SlqError::ArithmeticOverflow)?;
+        invoke_signed(
+            &system_instruction::create_account(
+                rent_payer.key,
+                proposal_pda.key,
+                rent_lamports,
+                space,
+                program_id,
+            ),
+            &[rent_payer.clone(), proposal_pda.clone()],
+            &[&[
+                b"proposal",
+                instance_pda.key.as_ref(),
+                self.proposal_name.as_ref(),
+                &[self.proposal_pda_bump_seed],
+            ]],
+        )?;
+
+        proposal.store(&mut proposal_pda.data.borrow_mut())?;
+
+        instance.open_proposals = instance
+            .open_proposals
+            .checked_add(1)
+            .ok_or(SlqError::ArithmeticOverflow)?;
+        instance.record_admin_action()?;
+        instance.store(&mut instance_pda.data.borrow_mut())?;
+
+        Ok(())
+    }
+}
+
+/// # Accounts
+///
+/// - 0: admin - signer, admin with the approver role
+/// - 1: instance_pda - pda
+/// - 2: proposal_pda - pda, writable
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct Approve {
+    instance_name: String,
+    proposal_name: String,
+    instance_pda_bump_seed: u8,
+    proposal_pda_bump_seed: u8,
+}
+
+impl Approve {
+    pub fn build_instruction(
+        program_id: &Pubkey,
+        admin: &Pubkey,
+        instance_name: String,
+        proposal_name: String,
+    ) -> Result<Instruction> {
+        let (instance_pda, instance_pda_bump_seed) = make_instance_pda(program_id, &instance_name);
+        let (proposal_pda, proposal_pda_bump_seed) =
+            make_proposal_pda(program_id, &instance_pda, &proposal_name);
+
+        let instr = SlqInstruction::Proposal(SlqProposalInstruction::Approve(Approve {
+            instance_name,
+            proposal_name,
+            instance_pda_bump_seed,
+            proposal_pda_bump_seed,
+        }));
+
+        let accounts = vec![
+            AccountMeta::new_readonly(*admin, true),
+            AccountMeta::new_readonly(instance_pda, false),
+            AccountMeta::new(proposal_pda, false),
+        ];
+
+        Ok(Instruction::new_with_borsh(*program_id, &instr, accounts))
+    }
+
+    fn exec(&self, program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+
+        let admin = next_account_info(accounts_iter)?;
+        let instance_pda = next_account_info(accounts_iter)?;
+        let proposal_pda = next_account_info(accounts_iter)?;
+
+        {
+            check::signer(admin, "admin")?;
+            check::owner(instance_pda, program_id, "instance_pda")?;
+            check::writable(proposal_pda, "proposal_pda")?;
+            check::owner(proposal_pda, program_id, "proposal_pda")?;
+
+            verify_pda(
+                program_id,
+                &self.instance_name,
+                instance_pda.key,
+                self.instance_pda_bump_seed,
+                make_instance_pda,
+            )?;
+            verify_pda(
+                program_id,
+                &self.proposal_name,
+                proposal_pda.key,
+                self.proposal_pda_bump_seed,
+                |program_id, proposal_name| {
+                    make_proposal_pda(program_id, instance_pda.key, proposal_name)
+                },
+            )?;
+        }
+
+        let instance = SlqInstance::load(&instance_pda.data.borrow())?;
+        let mut proposal = Proposal::load(&proposal_pda.data.borrow())?;
+        let clock = Clock::get()?;
+
+        verify_proposal_is_pending(&proposal, instance_pda.key)?;
+        verify_proposal_is_current(&proposal, &instance, &clock)?;
+
+        let index = verify_admin_role(
+            program_id,
+            &instance.admin_config,
+            admin.key,
+            Roles::APPROVER,
+        )?;
+        let bit = 1u64 << index;
+
+        if proposal.approvals & bit != 0 {
+            msg!("admin account {} already approved", admin.key);
+            return Err(SlqError::AlreadyApproved.into());
+        }
+
+        proposal.approvals |= bit;
+
+        if proposal.approved_at.is_none() {
+            let action = invoke_action(
+                program_id,
+                &instance.admin_config,
+                &proposal.instructions,
+                ApprovalAction::Proposal,
+            );
+            let approved_weight = instance.admin_config.approved_weight(proposal.approvals);
+            if approved_weight >= u32::from(instance.admin_config.threshold_for(action)) {
+                proposal.approved_at = Some(clock.unix_timestamp);
+            }
+        }
+
+        proposal.store(&mut proposal_pda.data.borrow_mut())?;
+
+        Ok(())
+    }
+}
+
+/// # Accounts
+///
+/// - 0: executor - signer, admin with the executor role
+/// - 1: instance_pda - pda, writable
+/// - 2: proposal_pda - pda, writable
+/// - 3: proposer - writable
+/// - 4..: every account and program referenced by the proposal's instructions
+///
+/// The proposal's instructions are invoked with the instance's authority pda
+/// as a signer. The threshold is that of `invoke_action` for them,
+/// with the proposal threshold for instructions the policy has no entry for.
+/// Once they have all succeeded the proposal account is closed,
+/// returning its rent to the proposer.
+///
+/// If the instance has an execution delay, it must have passed since the
+/// approvals met the threshold, as recorded by `Approve`.
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct Execute {
+    instance_name: String,
+    proposal_name: String,
+    instance_pda_bump_seed: u8,
+    proposal_pda_bump_seed: u8,
+    authority_pda_bump_seed: u8,
+}
+
+impl Execute {
+    pub fn build_instruction(
+        program_id: &Pubkey,
+        executor: &Pubkey,
+        instance_name: String,
+        proposal_name: String,
+        proposal: &Proposal,
+    ) -> Result<Instruction> {
+        let (instance_pda, instance_pda_bump_seed) = make_instance_pda(program_id, &instance_name);
+        let (proposal_pda, proposal_pda_bump_seed) =
+            make_proposal_pda(program_id, &instance_pda, &proposal_name);
+        let (authority_pda, authority_pda_bump_seed) =
+            make_authority_pda(program_id, &instance_pda);
+
+        let instr = SlqInstruction::Proposal(SlqProposalInstruction::Execute(Execute {
+            instance_name,
+            proposal_name,
+            instance_pda_bump_seed,
+            proposal_pda_bump_seed,
+            authority_pda_bump_seed,
+        }));
+
+        let mut accounts = vec![
+            AccountMeta::new_readonly(*executor, true),
+            AccountMeta::new(instance_pda, false),
+            AccountMeta::new(proposal_pda, false),
+            AccountMeta::new(proposal.proposer, false),
+        ];
+
+        for stored_instr in &proposal.instructions {
+            accounts.push(AccountMeta::new_readonly(stored_instr.program_id, false));
+            accounts.extend(stored_instr.accounts.iter().map(|meta| AccountMeta {
+                pubkey: meta.pubkey,
+                is_signer: meta.is_signer && meta.pubkey != authority_pda,
+                is_writable: meta.is_writable,
+            }));
+        }
+
+        Ok(Instruction::new_with_borsh(*program_id, &instr, accounts))
+    }
+
+    fn exec(&self, program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+
+        let executor = next_account_info(accounts_iter)?;
+        let instance_pda = next_account_info(accounts_iter)?;
+        let proposal_pda = next_account_info(accounts_iter)?;
+        let proposer = next_account_info(accounts_iter)?;
+
+        {
+            check::signer(executor, "executor")?;
+            check::writable(instance_pda, "instance_pda")?;
+            check::owner(instance_pda, program_id, "instance_pda")?;
+            check::writable(proposal_pda, "proposal_pda")?;
+            check::owner(proposal_pda, program_id, "proposal_pda")?;
+            check::writable(proposer, "proposer")?;
+
+            verify_pda(
+                program_id,
+                &self.instance_name,
+                instance_pda.key,
+                self.instance_pda_bump_seed,
+                make_instance_pda,
+            )?;
+            verify_pda(
+                program_id,
+                &self.proposal_name,
+                proposal_pda.key,
+                self.proposal_pda_bump_seed,
+                |program_id, proposal_name| {
+                    make_proposal_pda(program_id, instance_pda.key, proposal_name)
+                },
+            )?;
+            verify_authority_pda(program_id, instance_pda.key, self.authority_pda_bump_seed)?;
+        }
+
+        let mut instance = SlqInstance::load(&instance_pda.data.borrow())?;
+        let mut proposal = Proposal::load(&proposal_pda.data.borrow())?;
+        let clock = Clock::get()?;
+
+        verify_proposal_is_pending(&proposal, instance_pda.key)?;
+        verify_proposal_is_current(&proposal, &instance, &clock)?;
+        if proposal.proposer != *proposer.key {
+            msg!(
+                "proposal was proposed by {}, not {}",
+                proposal.proposer,
+                proposer.key
+            );
+            return Err(SlqError::WrongProposer.into());
+        }
+        verify_invoke_allowed(program_id, &instance, &proposal.instructions)?;
+        verify_admin_role(
+            program_id,
+            &instance.admin_config,
+            executor.key,
+            Roles::EXECUTOR,
+        )?;
+
+        let action = invoke_action(
+            program_id,
+            &instance.admin_config,
+            &proposal.instructions,
+            ApprovalAction::Proposal,
+        );
+        let approval_threshold = instance.admin_config.threshold_for(action);
+        let approved_weight = instance.admin_config.approved_weight(proposal.approvals);
+        if approved_weight < u32::from(approval_threshold) {
+            msg!(
+                "approved admin weight is {}, approval threshold for {:?} is {}",
+                approved_weight,
+                action,
+                approval_threshold
+            );
+            return Err(SlqError::ApprovalThresholdNotMet.into());
+        }
+
+        let now = clock.unix_timestamp;
+        let execution_delay = instance.admin_config.policy.execution_delay;
+        match proposal.approved_at {
+            None if execution_delay > 0 => {
+                msg!("proposal has no approval time, the execution delay hasn't started");
+                return Err(SlqError::TimelockNotElapsed.into());
+            }
+            Some(approved_at) if now < approved_at.saturating_add(execution_delay) => {
+                msg!(
+                    "proposal is executable at {}, it is {}",
+                    approved_at.saturating_add(execution_delay),
+                    now
+                );
+                return Err(SlqError::TimelockNotElapsed.into());
+            }
+            _ => {}
+        }
+
+        // Mark executed before invoking so the proposal can't be re-entered.
+        proposal.executed = true;
+        proposal.store(&mut proposal_pda.data.borrow_mut())?;
+
+        instance.open_proposals = instance
+            .open_proposals
+            .checked_sub(1)
+            .ok_or(SlqError::ArithmeticOverflow)?;
+        instance.record_admin_action()?;
+        instance.store(&mut instance_pda.data.borrow_mut())?;
+
+        for stored_instr in &proposal.instructions {
+            invoke_as_authority(
+                &Instruction::from(stored_instr),
+                accounts,
+                instance_pda.key,
+                self.authority_pda_bump_seed,
+            )?;
+        }
+
+        close_account(proposal_pda, proposer)
+    }
+}
+
+/// Cancels a pending proposal, closing its account.
+///
+/// This is how a proposal is stopped during the instance's execution delay,
+/// and how stale or expired proposals are cleaned up.
+///
+/// Only the instance's guardians and admins with the canceller or guardian role may cancel,
+/// so that those who can veto an approved proposal
+/// are chosen separately from those who can approve one.
+///
+/// # Accounts
+///
+/// - 0: canceller - signer, a guardian or an admin with the canceller or guardian role
+/// - 1: instance_pda - pda, writable
+/// - 2: proposal_pda - pda, writable
+/// - 3: recipient - writable
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct Cancel {
+    instance_name: String,
+    proposal_name: String,
+    instance_pda_bump_seed: u8,
+    proposal_pda_bump_seed: u8,
+}
+
+impl Cancel {
+    pub fn build_instruction(
+        program_id: &Pubkey,
+        canceller: &Pubkey,
+        instance_name: String,
+        proposal_name: String,
+        recipient: &Pubkey,
+    ) -> Result<Instruction> {
+        let (instance_pda, instance_pda_bump_seed) = make_instance_pda(program_id, &instance_name);
+        let (proposal_pda, proposal_pda_bump_seed) =
+            make_proposal_pda(program_id, &instance_pda, &proposal_name);
+
+        let instr = SlqInstruction::Proposal(SlqProposalInstruction::Cancel(Cancel {
+            instance_name,
+            proposal_name,
+            instance_pda_bump_seed,
+            proposal_pda_bump_seed,
+        }));
+
+        let accounts = vec![
+            AccountMeta::new_readonly(*canceller, true),
+            AccountMeta::new(instance_pda, false),
+            AccountMeta::new(proposal_pda, false),
+            AccountMeta::new(*recipient, false),
+        ];
+
+        Ok(Instruction::new_with_borsh(*program_id, &instr, accounts))
+    }
+
+    fn exec(&self, program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+
+        let canceller = next_account_info(accounts_iter)?;
+        let instance_pda = next_account_info(accounts_iter)?;
+        let proposal_pda = next_account_info(accounts_iter)?;
+        let recipient = next_account_info(accounts_iter)?;
+
+        {
+            check::signer(canceller, "canceller")?;
+            check::writable(instance_pda, "instance_pda")?;
+            check::owner(instance_pda, program_id, "instance_pda")?;
+            check::writable(proposal_pda, "proposal_pda")?;
+            check::owner(proposal_pda, program_id, "proposal_pda")?;
+            check::writable(recipient, "recipient")?;
+
+            verify_pda(
+                program_id,
+                &self.instance_name,
+                instance_pda.key,
+                self.instance_pda_bump_seed,
+                make_instance_pda,
+            )?;
+            verify_pda(
+                program_id,
+                &self.proposal_name,
+                proposal_pda.key,
+                self.proposal_pda_bump_seed,
+                |program_id, proposal_name| {
+                    make_proposal_pda(program_id, instance_pda.key, proposal_name)
+                },
+            )?;
+        }
+
+        let mut instance = SlqInstance::load(&instance_pda.data.borrow())?;
+        let proposal = Proposal::load(&proposal_pda.data.borrow())?;
+
+        verify_proposal_is_pending(&proposal, instance_pda.key)?;
+        let is_guardian = instance.guardians.contains(canceller.key);
+        if !is_guardian {
+            verify_admin_role(
+                program_id,
+                &instance.admin_config,
+                canceller.key,
+                Roles::CANCELLER.union(Roles::GUARDIAN),
+            )?;
+        }
+
+        instance.open_proposals = instance
+            .open_proposals
+            .checked_sub(1)
+            .ok_or(SlqError::ArithmeticOverflow)?;
+        // A guardian outside the admin set isn't admin activity.
+        if !is_guardian {
+            instance.record_admin_action()?;
+        }
+        instance.store(&mut instance_pda.data.borrow_mut())?;
+
+        close_account(proposal_pda, recipient)
+    }
+}
+
+pub fn make_proposal_pda(
+    program_id: &Pubkey,
+    instance_pda: &Pubkey,
+    proposal_name: &str,
+) -> (Pubkey, u8) {
+    let seeds = &[b"proposal", instance_pda.as_ref(), proposal_name.as_bytes()];
+    Pubkey::find_program_address(seeds, program_id)
+}
+
+fn verify_proposal_is_pending(proposal: &Proposal, instance_pda: &Pubkey) -> ProgramResult {
+    if proposal.instance != *instance_pda {
+        msg!("proposal belongs to instance {}", proposal.instance);
+        return Err(SlqError::WrongInstance.into());
+    }
+    if proposal.executed {
+        msg!("proposal has already been executed");
+        return Err(SlqError::ProposalAlreadyExecuted.into());
+    }
+
+    Ok(())
+}
+
+/// Verifies that the proposal's approvals still count and it hasn't expired.
+fn verify_proposal_is_current(
+    proposal: &Proposal,
+    instance: &SlqInstance,
+    clock: &Clock,
+) -> ProgramResult {
+    if proposal.config_epoch != instance.config_epoch {
+        msg!(
+            "proposal was created in config epoch {}, the instance is in epoch {}",
+            proposal.config_epoch,
+            instance.config_epoch
+        );
+        return Err(SlqError::StaleProposal.into());
+    }
+    if proposal.has_expired(clock) {
+        msg!("proposal expired at {:?}", proposal.expiry);
+        return Err(SlqError::ProposalExpired.into());
+    }
+
+    Ok(())
+}
@@ -0,0 +1,399 @@
+#![allow(unused)]
+
+use anyhow::{anyhow, bail, Result};
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::instruction::{AccountMeta, Instruction};
+use solana_program::log::sol_log_data;
+use solana_program::sysvar::Sysvar;
+use solana_program::{
+    account_info::{next_account_info, next_account_infos, AccountInfo},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    system_program,
+};
+use std::convert::{TryFrom, TryInto};
+
+use crate::admin::AdminConfigReplaced;
+use crate::check;
+use crate::error::SlqError;
+use crate::init::{make_instance_pda, resize_account, verify_pda};
+use crate::state::{AdminConfig, PendingRecovery, RecoveryConfig, SlqAccount, SlqInstance};
+use crate::validate;
+use crate::SlqInstruction;
+
+pub fn exec(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instr: SlqRecoveryInstruction,
+) -> ProgramResult {
+    match instr {
+        SlqRecoveryInstruction::StartRecovery(instr) => instr.exec(program_id, accounts),
+        SlqRecoveryInstruction::VetoRecovery(instr) => instr.exec(program_id, accounts),
+        SlqRecoveryInstruction::CompleteRecovery(instr) => instr.exec(program_id, accounts),
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub enum SlqRecoveryInstruction {
+    StartRecovery(StartRecovery),
+    VetoRecovery(VetoRecovery),
+    CompleteRecovery(CompleteRecovery),
+}
+
+/// Starts replacing the admin config of an instance whose admins
+/// have been inactive for the recovery config's inactivity period.
+///
+/// The instance account grows to hold the pending recovery,
+/// with the extra rent paid by `rent_payer`.
+///
+/// # Accounts
+///
+/// - 0: rent_payer - writable, signer
+/// - 1: instance_pda - pda, writable
+/// - 2: system_program - executable
+/// - 3..: recovery_accounts - signer, distinct recovery accounts meeting the recovery threshold
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct StartRecovery {
+    instance_name: String,
+    admin_config: AdminConfig,
+    instance_pda_bump_seed: u8,
+}
+
+impl StartRecovery {
+    pub fn build_instruction(
+        program_id: &Pubkey,
+        rent_payer: &Pubkey,
+        recovery_pubkeys: &[Pubkey],
+        instance_name: String,
+        admin_config: AdminConfig,
+    ) -> Result<Instruction> {
+        let (instance_pda, instance_pda_bump_seed) = make_instance_pda(program_id, &instance_name);
+
+        let instr =
+            SlqInstruction::Recovery(SlqRecoveryInstruction::StartRecovery(StartRecovery {
+                instance_name,
+                admin_config,
+                instance_pda_bump_seed,
+            }));
+
+        let mut accounts = vec![
+            AccountMeta::new(*rent_payer, true),
+            AccountMeta::new(instance_pda, false),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ];
+
+        accounts.extend(
+            recovery_pubkeys
+                .iter()
+                .map(|recovery_pubkey| AccountMeta::new_readonly(*recovery_pubkey, true)),
+        );
+
+        Ok(Instruction::new_with_borsh(*program_id, &instr, accounts))
+    }
+
+    fn exec(&self, program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+
+        let rent_payer = next_account_info(accounts_iter)?;
+        let instance_pda = next_account_info(accounts_iter)?;
+        let system_program = next_account_info(accounts_iter)?;
+
+        {
+            check::writable(rent_payer, "rent_payer")?;
+            check::signer(rent_payer, "rent_payer")?;
+            check::writable(instance_pda, "instance_pda")?;
+            check::owner(instance_pda, program_id, "instance_pda")?;
+            check::system_program(system_program)?;
+
+            verify_pda(
+                program_id,
+                &self.instance_name,
+                instance_pda.key,
+                self.instance_pda_bump_seed,
+                make_instance_pda,
+            )?;
+        }
+
+        let mut instance = SlqInstance::load(&instance_pda.data.borrow())?;
+        let now = Clock::get()?.unix_timestamp;
+
+        let recovery = match &instance.recovery {
+            Some(recovery) => recovery,
+            None => {
+                msg!("instance has no recovery config");
+                return Err(SlqError::RecoveryNotConfigured.into());
+            }
+        };
+
+        if instance.pending_recovery.is_some() {
+            msg!("a recovery is already pending, it must be vetoed or completed first");
+            return Err(SlqError::RecoveryInProgress.into());
+        }
+
+        let inactive_from = instance
+            .last_admin_action
+            .saturating_add(recovery.inactivity_period);
+        if now < inactive_from {
+            msg!("admins are inactive from {}, it is {}", inactive_from, now);
+            return Err(SlqError::AdminsStillActive.into());
+        }
+
+        verify_recovery_signers(recovery, accounts_iter.as_slice())?;
+        validate::admin_config(&self.admin_config)?;
+
+        instance.pending_recovery = Some(PendingRecovery {
+            admin_config: self.admin_config.clone(),
+            started_at: now,
+        });
+
+        resize_account(
+            instance_pda,
+            rent_payer,
+            system_program,
+            instance.packed_len()?,
+        )?;
+        instance.store(&mut instance_pda.data.borrow_mut())?;
+
+        Ok(())
+    }
+}
+
+/// Drops a pending recovery, on the say of any single admin.
+///
+/// The instance account shrinks, refunding the freed rent to `admin`.
+///
+/// # Accounts
+///
+/// - 0: admin - writable, signer, any admin of the instance
+/// - 1: instance_pda - pda, writable
+/// - 2: system_program - executable
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct VetoRecovery {
+    instance_name: String,
+    instance_pda_bump_seed: u8,
+}
+
+impl VetoRecovery {
+    pub fn build_instruction(
+        program_id: &Pubkey,
+        admin: &Pubkey,
+        instance_name: String,
+    ) -> Result<Instruction> {
+        let (instance_pda, instance_pda_bump_seed) = make_instance_pda(program_id, &instance_name);
+
+        let instr = SlqInstruction::Recovery(SlqRecoveryInstruction::VetoRecovery(VetoRecovery {
+            instance_name,
+            instance_pda_bump_seed,
+        }));
+
+        let accounts = vec![
+            AccountMeta::new(*admin, true),
+            AccountMeta::new(instance_pda, false),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ];
+
+        Ok(Instruction::new_with_borsh(*program_id, &instr, accounts))
+    }
+
+    fn exec(&self, program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+
+        let admin = next_account_info(accounts_iter)?;
+        let instance_pda = next_account_info(accounts_iter)?;
+        let system_program = next_account_info(accounts_iter)?;
+
+        {
+            check::writable(admin, "admin")?;
+            check::signer(admin, "admin")?;
+            check::writable(instance_pda, "instance_pda")?;
+            check::owner(instance_pda, program_id, "instance_pda")?;
+            check::system_program(system_program)?;
+
+            verify_pda(
+                program_id,
+                &self.instance_name,
+                instance_pda.key,
+                self.instance_pda_bump_seed,
+                make_instance_pda,
+            )?;
+        }
+
+        let mut instance = SlqInstance::load(&instance_pda.data.borrow())?;
+
+        if instance
+            .admin_config
+            .signer_position(program_id, admin.key)
+            .is_none()
+        {
+            msg!("{} is not an admin account", admin.key);
+            return Err(SlqError::NotAnAdmin.into());
+        }
+
+        if instance.pending_recovery.take().is_none() {
+            msg!("instance has no pending recovery");
+            return Err(SlqError::NoPendingRecovery.into());
+        }
+
+        msg!("recovery vetoed by admin {}", admin.key);
+        instance.record_admin_action()?;
+
+        resize_account(instance_pda, admin, system_program, instance.packed_len()?)?;
+        instance.store(&mut instance_pda.data.borrow_mut())?;
+
+        Ok(())
+    }
+}
+
+/// Replaces the admin config with the pending recovery's
+/// once its timelock has elapsed without a veto.
+///
+/// Anyone may complete a recovery.
+/// The replaced config is logged as an `AdminConfigReplaced` event.
+///
+/// # Accounts
+///
+/// - 0: rent_payer - writable, signer
+/// - 1: instance_pda - pda, writable
+/// - 2: system_program - executable
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct CompleteRecovery {
+    instance_name: String,
+    instance_pda_bump_seed: u8,
+}
+
+impl CompleteRecovery {
+    pub fn build_instruction(
+        program_id: &Pubkey,
+        rent_payer: &Pubkey,
+        instance_name: String,
+    ) -> Result<Instruction> {
+        let (instance_pda, instance_pda_bump_seed) = make_instance_pda(program_id, &instance_name);
+
+        let instr =
+            SlqInstruction::Recovery(SlqRecoveryInstruction::CompleteRecovery(CompleteRecovery {
+                instance_name,
+                instance_pda_bump_seed,
+            }));
+
+        let accounts = vec![
+            AccountMeta::new(*rent_payer, true),
+            AccountMeta::new(instance_pda, false),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ];
+
+        Ok(Instruction::new_with_borsh(*program_id, &instr, accounts))
+    }
+
+    fn exec(&self, program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+
+        let rent_payer = next_account_info(accounts_iter)?;
+        let instance_pda = next_account_info(accounts_iter)?;
+        let system_program = next_account_info(accounts_iter)?;
+
+        {
+            check::writable(rent_payer, "rent_payer")?;
+            check::signer(rent_payer, "rent_payer")?;
+            check::writable(instance_pda, "instance_pda")?;
+            check::owner(instance_pda, program_id, "instance_pda")?;
+            check::system_program(system_program)?;
+
+            verify_pda(
+                program_id,
+                &self.instance_name,
+                instance_pda.key,
+                self.instance_pda_bump_seed,
+                make_instance_pda,
+            )?;
+        }
+
+        let mut instance = SlqInstance::load(&instance_pda.data.borrow())?;
+        let now = Clock::get()?.unix_timestamp;
+
+        let pending_recovery = match instance.pending_recovery.take() {
+            Some(pending_recovery) => pending_recovery,
+            None => {
+                msg!("instance has no pending recovery");
+                return Err(SlqError::NoPendingRecovery.into());
+            }
+        };
+
+        // The recovery config can only have been removed along with the pending recovery.
+        let timelock = instance
+            .recovery
+            .as_ref()
+            .map_or(0, |recovery| recovery.timelock);
+        let completes_at = pending_recovery.started_at.saturating_add(timelock);
+        if now < completes_at {
+            msg!("recovery completes at {}, it is {}", completes_at, now);
+            return Err(SlqError::TimelockNotElapsed.into());
+        }
+
+        let old_admin_config =
+            std::mem::replace(&mut instance.admin_config, pending_recovery.admin_config);
+        instance.pending_admin = None;
+        instance.bump_config_epoch()?;
+        instance.record_admin_action()?;
+
+        let event = AdminConfigReplaced {
+            instance: *instance_pda.key,
+            old_admin_config,
+            config_epoch: instance.config_epoch,
+        };
+        msg!("recovered admin config: {:?}", event);
+        sol_log_data(&[&borsh::to_vec(&event)?]);
+
+        resize_account(
+            instance_pda,
+            rent_payer,
+            system_program,
+            instance.packed_len()?,
+        )?;
+        instance.store(&mut instance_pda.data.borrow_mut())?;
+
+        Ok(())
+    }
+}
+
+/// Verifies that distinct members of `recovery.recovery_accounts`
+/// meeting the recovery threshold signed.
+fn verify_recovery_signers(
+    recovery: &RecoveryConfig,
+    recovery_signers: &[AccountInfo],
+) -> ProgramResult {
+    let mut approvals: Vec<Pubkey> = Vec::with_capacity(recovery_signers.len());
+
+    for recovery_signer in recovery_signers {
+        if !recovery_signer.is_signer {
+            msg!("recovery account {} did not sign", recovery_signer.key);
+            return Err(SlqError::MissingSigner.into());
+        }
+        if !recovery.recovery_accounts.contains(recovery_signer.key) {
+            msg!("{} is not a recovery account", recovery_signer.key);
+            return Err(SlqError::NotARecoveryAccount.into());
+        }
+        if approvals.contains(recovery_signer.key) {
+            msg!(
+                "recovery account {} signed more than once",
+                recovery_signer.key
+            );
+            return Err(SlqError::DuplicateAdminSigner.into());
+        }
+
+        approvals.push(*recovery_signer.key);
+    }
+
+    if approvals.len() < usize::from(recovery.threshold) {
+        msg!(
+            "{} recovery accounts signed, the recovery threshold is {}",
+            approvals.len(),
+            recovery.threshold
+        );
+        return Err(SlqError::ApprovalThresholdNotMet.into());
+    }
+
+    Ok(())
+}
@@ -2,33 +2,672 @@
 
 use anyhow::{anyhow, Result};
 use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::borsh1::get_instance_packed_len;
 use solana_program::instruction::{AccountMeta, Instruction};
 use solana_program::{
     account_info::{next_account_info, next_account_infos, AccountInfo},
+    clock::Clock,
     entrypoint::ProgramResult,
     msg,
     program::invoke_signed,
     program_error::ProgramError,
     pubkey::Pubkey,
     system_instruction, system_program,
+    sysvar::Sysvar,
 };
 use std::convert::{TryFrom, TryInto};
+use std::fmt;
+use std::str::FromStr;
+
+use crate::error::SlqError;
+
+/// Identifies the type of an slq-owned account.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccountType {
+    Instance,
+    MultisigConfig,
+    Proposal,
+    Vault,
+}
+
+/// Written before the Borsh-encoded body of every slq-owned account.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AccountHeader {
+    pub account_type: AccountType,
+    pub version: u8,
+}
+
+pub const ACCOUNT_HEADER_LEN: usize = 2;
+
+/// An account type stored with an `AccountHeader`.
+///
+/// `VERSION` is bumped whenever the layout changes,
+/// and `migrate::MigrateAccount` upgrades accounts from older versions.
+pub trait SlqAccount: BorshSerialize + BorshDeserialize {
+    const ACCOUNT_TYPE: AccountType;
+    const VERSION: u8;
+
+    /// Deserializes the account, failing unless the header matches
+    /// `ACCOUNT_TYPE` and `VERSION`.
+    fn load(data: &[u8]) -> Result<Self, ProgramError> {
+        let header = read_header(data)?;
+        if header.account_type != Self::ACCOUNT_TYPE {
+            msg!(
+                "expected {:?} account, got {:?}",
+                Self::ACCOUNT_TYPE,
+                header.account_type
+            );
+            return Err(SlqError::WrongAccountType.into());
+        }
+        if header.version != Self::VERSION {
+            msg!(
+                "expected account version {}, got {}, the account must be migrated",
+                Self::VERSION,
+                header.version
+            );
+            return Err(SlqError::WrongAccountVersion.into());
+        }
+
+        Ok(Self::deserialize(&mut &data[ACCOUNT_HEADER_LEN..])?)
+    }
+
+    /// Serializes the account with the current header.
+    fn store(&self, data: &mut [u8]) -> ProgramResult {
+        let mut writer = data;
+        let header = AccountHeader {
+            account_type: Self::ACCOUNT_TYPE,
+            version: Self::VERSION,
+        };
+        header.serialize(&mut writer)?;
+        self.serialize(&mut writer)?;
+
+        Ok(())
+    }
+
+    /// The account size needed to store this value, including the header.
+    fn packed_len(&self) -> Result<usize, ProgramError> {
+        Ok(ACCOUNT_HEADER_LEN + get_instance_packed_len(self)?)
+    }
+}
+
+pub fn read_header(data: &[u8]) -> Result<AccountHeader, ProgramError> {
+    if data.len() < ACCOUNT_HEADER_LEN {
+        msg!("account data is too short for an account header");
+        return Err(SlqError::WrongAccountType.into());
+    }
+
+    AccountHeader::try_from_slice(&data[..ACCOUNT_HEADER_LEN]).map_err(|_| {
+        msg!("unknown account type {}", data[0]);
+        SlqError::WrongAccountType.into()
+    })
+}
 
 #[derive(BorshSerialize, BorshDeserialize, Debug, Default)]
 pub struct SlqInstance {
     pub admin_config: AdminConfig,
+    /// Proposals created and not yet executed.
+    ///
+    /// The instance can't be destroyed while this is non-zero.
+    pub open_proposals: u32,
+    /// Incremented whenever `admin_config` or `recovery` changes.
+    ///
+    /// Proposals created in an earlier epoch are stale and can only be cancelled,
+    /// as their approvals were given under a different admin set.
+    pub config_epoch: u64,
+    /// An admin nominated by the existing admins,
+    /// added to `admin_config` once it accepts.
+    pub pending_admin: Option<AdminAccount>,
+    /// Set by a guardian in an emergency.
+    ///
+    /// While paused, vault withdrawals and proposal execution are refused,
+    /// other than proposals that only change the admin set.
+    pub paused: bool,
+    /// Accounts outside the admin set that may pause the instance,
+    /// alongside admins with the guardian role.
+    pub guardians: Vec<Pubkey>,
+    /// Unix timestamp of the last admin action that wrote the instance.
+    ///
+    /// Recovery may start once the admins have been inactive for the
+    /// recovery config's inactivity period.
+    pub last_admin_action: i64,
+    pub recovery: Option<RecoveryConfig>,
+    /// A replacement admin config started by the recovery accounts,
+    /// which any admin may veto until its timelock elapses.
+    pub pending_recovery: Option<PendingRecovery>,
+    /// Vaults created for the instance.
+    ///
+    /// The instance can't be destroyed while this is non-zero,
+    /// as a new instance of the same name would govern its vaults.
+    pub vaults: u32,
 }
 
-pub const MAX_ADMIN_ACCOUNTS: usize = 6;
+impl SlqAccount for SlqInstance {
+    const ACCOUNT_TYPE: AccountType = AccountType::Instance;
+    const VERSION: u8 = 1;
+}
 
-#[derive(BorshSerialize, BorshDeserialize, Debug, Default)]
+impl SlqInstance {
+    pub fn bump_config_epoch(&mut self) -> ProgramResult {
+        self.config_epoch = self
+            .config_epoch
+            .checked_add(1)
+            .ok_or(SlqError::ArithmeticOverflow)?;
+
+        Ok(())
+    }
+
+    pub fn record_admin_action(&mut self) -> ProgramResult {
+        self.last_admin_action = Clock::get()?.unix_timestamp;
+
+        Ok(())
+    }
+
+    pub fn verify_not_paused(&self) -> ProgramResult {
+        if self.paused {
+            msg!("instance is paused, it must be unpaused first");
+            return Err(SlqError::InstancePaused.into());
+        }
+
+        Ok(())
+    }
+}
+
+/// Lets a separate set of keys replace the admin config
+/// of an instance whose admins have gone inactive.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq, Eq)]
+pub struct RecoveryConfig {
+    pub recovery_accounts: Vec<Pubkey>,
+    /// The number of recovery accounts that must sign to start a recovery.
+    pub threshold: u8,
+    /// Seconds without an admin action before recovery may start.
+    pub inactivity_period: i64,
+    /// Seconds between recovery starting and it completing,
+    /// during which any admin may veto it.
+    pub timelock: i64,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+pub struct PendingRecovery {
+    pub admin_config: AdminConfig,
+    /// Unix timestamp of the start of the recovery.
+    pub started_at: i64,
+}
+
+/// The ceiling for `AdminConfig::max_admin_accounts`.
+///
+/// Proposal approvals are a bitmap over admin indexes, so this can't exceed 64.
+pub const MAX_ADMIN_ACCOUNTS: usize = 64;
+
+/// The most accounts `SlqInstance::guardians` may hold.
+pub const MAX_GUARDIANS: usize = 16;
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Default)]
 pub struct AdminConfig {
+    /// The sum of admin weights required to approve an instruction
+    /// that `policy` doesn't set a threshold for.
     pub approval_threshold: u8,
-    pub admin_accounts: [Pubkey; MAX_ADMIN_ACCOUNTS],
+    /// The most admin accounts this instance may have, set at `Init`.
+    pub max_admin_accounts: u8,
+    pub admin_accounts: Vec<AdminAccount>,
+    pub policy: ApprovalPolicy,
+}
+
+/// Approval thresholds for specific kinds of action.
+///
+/// A threshold of 0 means the action needs `AdminConfig::approval_threshold`.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ApprovalPolicy {
+    /// Adding and removing admin accounts.
+    pub admin_set_threshold: u8,
+    /// Changing `approval_threshold` or this policy.
+    pub threshold_change_threshold: u8,
+    /// Vault withdrawals of more than `vault_withdrawal_limit`.
+    pub vault_withdrawal_threshold: u8,
+    pub vault_withdrawal_limit: u64,
+    /// Instructions invoking the upgradeable bpf loader.
+    pub program_upgrade_threshold: u8,
+    /// Executing any other proposal.
+    pub proposal_threshold: u8,
+    /// Seconds between a proposal meeting its threshold and it becoming executable.
+    ///
+    /// While non-zero, admin instructions are only approved through proposals,
+    /// so every change is delayed and can be cancelled.
+    pub execution_delay: i64,
+}
+
+/// The kinds of action `ApprovalPolicy` sets thresholds for.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApprovalAction {
+    /// Anything the policy has no entry for.
+    Default,
+    AdminSetChange,
+    ThresholdChange,
+    VaultWithdrawal {
+        amount: u64,
+    },
+    ProgramUpgrade,
+    Proposal,
+    /// Lifting a guardian's pause, which needs the highest threshold of any action.
+    Unpause,
+    /// Withdrawing SPL tokens from a vault, which needs the vault withdrawal threshold
+    /// whatever the amount, as `vault_withdrawal_limit` is in lamports.
+    TokenWithdrawal,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AdminAccount {
+    pub pubkey: Pubkey,
+    /// How much this admin's approval counts towards `approval_threshold`.
+    pub weight: u8,
+    pub roles: Roles,
+    pub kind: AdminKind,
+}
+
+/// What signs for an admin account.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AdminKind {
+    /// The admin's pubkey signs itself.
+    Key,
+    /// The admin's pubkey is another slq instance,
+    /// which approves through its authority pda once its own admins have.
+    Instance { authority_pda_bump_seed: u8 },
+}
+
+impl AdminAccount {
+    /// The account whose signature counts as this admin's approval,
+    /// or `None` if an instance's bump seed derives no authority pda.
+    pub fn signer(&self, program_id: &Pubkey) -> Option<Pubkey> {
+        match self.kind {
+            AdminKind::Key => Some(self.pubkey),
+            AdminKind::Instance {
+                authority_pda_bump_seed,
+            } => Pubkey::create_program_address(
+                &[
+                    b"authority",
+                    self.pubkey.as_ref(),
+                    &[authority_pda_bump_seed],
+                ],
+                program_id,
+            )
+            .ok(),
+        }
+    }
+
+    pub fn has_role(&self, role: Roles) -> bool {
+        self.roles.contains(role)
+    }
+
+    pub fn has_any_role(&self, roles: Roles) -> bool {
+        self.roles.intersects(roles)
+    }
+}
+
+/// The duties an admin may perform, as a set of flags.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Roles(pub u8);
+
+impl Roles {
+    pub const NONE: Roles = Roles(0);
+    /// May create proposals.
+    pub const PROPOSER: Roles = Roles(1 << 0);
+    /// May approve proposals and sign admin instructions.
+    pub const APPROVER: Roles = Roles(1 << 1);
+    /// May execute approved proposals.
+    pub const EXECUTOR: Roles = Roles(1 << 2);
+    /// May cancel pending proposals.
+    pub const CANCELLER: Roles = Roles(1 << 3);
+    /// May cancel pending proposals, and take other emergency actions.
+    pub const GUARDIAN: Roles = Roles(1 << 4);
+    pub const ALL: Roles = Roles(0b1_1111);
+
+    const NAMES: &'static [(Roles, &'static str)] = &[
+        (Roles::PROPOSER, "proposer"),
+        (Roles::APPROVER, "approver"),
+        (Roles::EXECUTOR, "executor"),
+        (Roles::CANCELLER, "canceller"),
+        (Roles::GUARDIAN, "guardian"),
+    ];
+
+    pub fn contains(self, roles: Roles) -> bool {
+        self.0 & roles.0 == roles.0
+    }
+
+    pub fn intersects(self, roles: Roles) -> bool {
+        self.0 & roles.0 != 0
+    }
+
+    pub fn union(self, roles: Roles) -> Roles {
+        Roles(self.0 | roles.0)
+    }
+
+    pub fn is_valid(self) -> bool {
+        self.0 & !Roles::ALL.0 == 0
+    }
+}
+
+impl Default for Roles {
+    fn default() -> Roles {
+        Roles::ALL
+    }
+}
+
+impl fmt::Display for Roles {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names: Vec<&str> = Roles::NAMES
+            .iter()
+            .filter(|(role, _)| self.contains(*role))
+            .map(|(_, name)| *name)
+            .collect();
+
+        if names.is_empty() {
+            write!(f, "none")
+        } else {
+            write!(f, "{}", names.join(","))
+        }
+    }
+}
+
+/// Parses a comma-separated list of role names, or `all` or `none`.
+impl FromStr for Roles {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Roles> {
+        match s {
+            "all" => return Ok(Roles::ALL),
+            "none" => return Ok(Roles::NONE),
+            _ => {}
+        }
+
+        s.split(',').try_fold(Roles::NONE, |roles, name| {
+            Roles::NAMES
+                .iter()
+                .find(|(_, role_name)| *role_name == name)
+                .map(|(role, _)| roles.union(*role))
+                .ok_or_else(|| anyhow!("unknown admin role {}", name))
+        })
+    }
+}
+
+impl AdminConfig {
+    /// The sum of admin weights required to approve `action`.
+    pub fn threshold_for(&self, action: ApprovalAction) -> u8 {
+        let policy = &self.policy;
+        let threshold = match action {
+            ApprovalAction::Default => 0,
+            ApprovalAction::AdminSetChange => policy.admin_set_threshold,
+            ApprovalAction::ThresholdChange => policy.threshold_change_threshold,
+            ApprovalAction::VaultWithdrawal { amount } => {
+                if amount > policy.vault_withdrawal_limit {
+                    policy.vault_withdrawal_threshold
+                } else {
+                    0
+                }
+            }
+            ApprovalAction::TokenWithdrawal => policy.vault_withdrawal_threshold,
+            ApprovalAction::ProgramUpgrade => policy.program_upgrade_threshold,
+            ApprovalAction::Proposal => policy.proposal_threshold,
+            ApprovalAction::Unpause => return self.highest_threshold(),
+        };
+
+        if threshold == 0 {
+            self.approval_threshold
+        } else {
+            threshold
+        }
+    }
+
+    /// Whichever of `a` and `b` needs the higher threshold, `a` if they tie.
+    pub fn stricter(&self, a: ApprovalAction, b: ApprovalAction) -> ApprovalAction {
+        if self.threshold_for(b) > self.threshold_for(a) {
+            b
+        } else {
+            a
+        }
+    }
+
+    /// The largest threshold any action may need.
+    pub fn highest_threshold(&self) -> u8 {
+        let policy = &self.policy;
+        [
+            self.approval_threshold,
+            policy.admin_set_threshold,
+            policy.threshold_change_threshold,
+            policy.vault_withdrawal_threshold,
+            policy.program_upgrade_threshold,
+            policy.proposal_threshold,
+        ]
+        .iter()
+        .copied()
+        .max()
+        .unwrap_or_default()
+    }
+
+    pub fn position(&self, pubkey: &Pubkey) -> Option<usize> {
+        self.admin_accounts
+            .iter()
+            .position(|admin_account| admin_account.pubkey == *pubkey)
+    }
+
+    pub fn contains(&self, pubkey: &Pubkey) -> bool {
+        self.position(pubkey).is_some()
+    }
+
+    /// The index of the admin that `signer` approves for.
+    pub fn signer_position(&self, program_id: &Pubkey, signer: &Pubkey) -> Option<usize> {
+        self.admin_accounts
+            .iter()
+            .position(|admin_account| admin_account.signer(program_id) == Some(*signer))
+    }
+
+    pub fn get(&self, pubkey: &Pubkey) -> Option<&AdminAccount> {
+        self.position(pubkey)
+            .map(|index| &self.admin_accounts[index])
+    }
+
+    pub fn weight_of(&self, pubkey: &Pubkey) -> Option<u8> {
+        self.get(pubkey).map(|admin_account| admin_account.weight)
+    }
+
+    /// The summed weight of the admins that hold the approver role.
+    pub fn total_weight(&self) -> u32 {
+        self.admin_accounts
+            .iter()
+            .filter(|admin_account| admin_account.has_role(Roles::APPROVER))
+            .map(|admin_account| u32::from(admin_account.weight))
+            .sum()
+    }
+
+    /// The summed weight of the approvers whose bits are set in `approvals`.
+    pub fn approved_weight(&self, approvals: u64) -> u32 {
+        self.admin_accounts
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| approvals & (1u64 << index) != 0)
+            .filter(|(_, admin_account)| admin_account.has_role(Roles::APPROVER))
+            .map(|(_, admin_account)| u32::from(admin_account.weight))
+            .sum()
+    }
 }
 
 #[derive(BorshSerialize, BorshDeserialize, Debug, Default)]
 pub struct MultisigConfigInstance {
     pub approval_threshold: u8,
-    pub owners: [Pubkey; crate::multisig::MAX_MULTISIG_OWNERS],
+    pub owners: Vec<Pubkey>,
+}
+
+impl SlqAccount for MultisigConfigInstance {
+    const ACCOUNT_TYPE: AccountType = AccountType::MultisigConfig;
+    const VERSION: u8 = 1;
+}
+
+/// The state of a vault, stored in the vault pda alongside its lamports.
+///
+/// The vault's instance governs withdrawals,
+/// including of the tokens in the vault's associated token accounts.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Default)]
+pub struct Vault {
+    /// The account that created the vault and paid its rent.
+    pub creator: Pubkey,
+    pub name: String,
+    pub bump_seed: u8,
+    pub instance: Pubkey,
+    /// The slot the vault was created in.
+    pub created_slot: u64,
+    /// At most one per mint, and one for lamports.
+    pub spending_limits: Vec<SpendingLimit>,
+}
+
+impl SlqAccount for Vault {
+    const ACCOUNT_TYPE: AccountType = AccountType::Vault;
+    const VERSION: u8 = 1;
+}
+
+impl Vault {
+    /// The spending limit for `mint`, or for lamports if `None`.
+    pub fn spending_limit_mut(&mut self, mint: Option<Pubkey>) -> Option<&mut SpendingLimit> {
+        self.spending_limits
+            .iter_mut()
+            .find(|spending_limit| spending_limit.mint == mint)
+    }
+}
+
+/// Lets a single admin withdraw up to `amount` from a vault per period,
+/// without meeting the instance's withdrawal threshold.
+///
+/// The limit is shared by all admins.
+/// Periods are fixed windows following on from the first one, not a rolling window,
+/// so up to twice `amount` can be withdrawn within `period` seconds
+/// spanning the end of one period and the start of the next.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpendingLimit {
+    /// The mint of the tokens limited, `None` for lamports.
+    pub mint: Option<Pubkey>,
+    /// In lamports, or the mint's smallest unit.
+    pub amount: u64,
+    /// In seconds.
+    pub period: i64,
+    /// Unix timestamp the current period started at.
+    pub period_start: i64,
+    /// Withdrawn by single admins since `period_start`.
+    pub spent: u64,
+}
+
+impl SpendingLimit {
+    /// Records withdrawing `amount` at `now`,
+    /// first moving on to the period `now` is in if the current one has passed.
+    ///
+    /// Returns false without recording it if that would exceed the limit.
+    pub fn try_spend(&mut self, now: i64, amount: u64) -> bool {
+        let elapsed = now.saturating_sub(self.period_start);
+        if self.period > 0 && elapsed >= self.period {
+            // Starting the new period at `now` would let withdrawals
+            // push back the end of each period.
+            self.period_start = self
+                .period_start
+                .saturating_add(elapsed - elapsed % self.period);
+            self.spent = 0;
+        }
+
+        match self.spent.checked_add(amount) {
+            Some(spent) if spent <= self.amount => {
+                self.spent = spent;
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Default)]
+pub struct Proposal {
+    pub instance: Pubkey,
+    pub proposer: Pubkey,
+    pub instructions: Vec<StoredInstruction>,
+    /// Bit `i` is set once `admin_accounts[i]` has approved.
+    pub approvals: u64,
+    pub executed: bool,
+    /// When the approvals first met the proposal's threshold,
+    /// starting the instance's execution delay.
+    pub approved_at: Option<i64>,
+    /// The instance's `config_epoch` when the proposal was created.
+    pub config_epoch: u64,
+    /// After this the proposal can no longer be approved or executed.
+    pub expiry: Option<Expiry>,
+}
+
+impl SlqAccount for Proposal {
+    const ACCOUNT_TYPE: AccountType = AccountType::Proposal;
+    const VERSION: u8 = 1;
+}
+
+impl Proposal {
+    pub fn has_expired(&self, clock: &Clock) -> bool {
+        self.expiry.is_some_and(|expiry| expiry.has_passed(clock))
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Expiry {
+    Slot(u64),
+    UnixTimestamp(i64),
+}
+
+impl Expiry {
+    pub fn has_passed(&self, clock: &Clock) -> bool {
+        match *self {
+            Expiry::Slot(slot) => clock.slot > slot,
+            Expiry::UnixTimestamp(timestamp) => clock.unix_timestamp > timestamp,
+        }
+    }
+}
+
+/// An `Instruction` saved in account data to be invoked later.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+pub struct StoredInstruction {
+    pub program_id: Pubkey,
+    pub accounts: Vec<StoredAccountMeta>,
+    pub data: Vec<u8>,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+pub struct StoredAccountMeta {
+    pub pubkey: Pubkey,
+    pub is_signer: bool,
+    pub is_writable: bool,
+}
+
+impl From<&Instruction> for StoredInstruction {
+    fn from(instr: &Instruction) -> StoredInstruction {
+        StoredInstruction {
+            program_id: instr.program_id,
+            accounts: instr
+                .accounts
+                .iter()
+                .map(|meta| StoredAccountMeta {
+                    pubkey: meta.pubkey,
+                    is_signer: meta.is_signer,
+                    is_writable: meta.is_writable,
+                })
+                .collect(),
+            data: instr.data.clone(),
+        }
+    }
+}
+
+impl From<&StoredInstruction> for Instruction {
+    fn from(instr: &StoredInstruction) -> Instruction {
+        Instruction {
+            program_id: instr.program_id,
+            accounts: instr
+                .accounts
+                .iter()
+                .map(|meta| AccountMeta {
+                    pubkey: meta.pubkey,
+                    is_signer: meta.is_signer,
+                    is_writable: meta.is_writable,
+                })
+                .collect(),
+            data: instr.data.clone(),
+        }
+    }
 }
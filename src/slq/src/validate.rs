@@ -0,0 +1,181 @@
+//! Admin config, recovery config, guardian and spending limit invariants
+//! shared by the program and its clients.
+//!
+//! The program validates every admin config it is about to store,
+//! and clients validate the config an instruction would produce
+//! before sending it, so both refuse the same changes.
+//!
+//! Each check logs the reason before failing.
+
+use solana_program::msg;
+use solana_program::pubkey::Pubkey;
+
+use crate::error::SlqError;
+use crate::state::{AdminConfig, RecoveryConfig, SpendingLimit, MAX_ADMIN_ACCOUNTS, MAX_GUARDIANS};
+
+/// Validates a complete admin config.
+pub fn admin_config(admin_config: &AdminConfig) -> Result<(), SlqError> {
+    admin_accounts(admin_config)?;
+    thresholds(admin_config)?;
+
+    if admin_config.policy.execution_delay < 0 {
+        msg!(
+            "execution delay {} must not be negative",
+            admin_config.policy.execution_delay
+        );
+        return Err(SlqError::InvalidExecutionDelay);
+    }
+
+    Ok(())
+}
+
+/// Validates the admin list: its size, and each admin's weight and roles.
+pub fn admin_accounts(admin_config: &AdminConfig) -> Result<(), SlqError> {
+    let admin_accounts = &admin_config.admin_accounts;
+
+    if usize::from(admin_config.max_admin_accounts) > MAX_ADMIN_ACCOUNTS {
+        msg!(
+            "maximum of {} admin accounts is above the ceiling of {}",
+            admin_config.max_admin_accounts,
+            MAX_ADMIN_ACCOUNTS
+        );
+        return Err(SlqError::TooManyAdminAccounts);
+    }
+
+    if admin_accounts.len() > usize::from(admin_config.max_admin_accounts) {
+        msg!(
+            "{} admin accounts, the maximum is {}",
+            admin_accounts.len(),
+            admin_config.max_admin_accounts
+        );
+        return Err(SlqError::TooManyAdminAccounts);
+    }
+
+    if admin_accounts.is_empty() {
+        msg!("must have at least 1 admin account");
+        return Err(SlqError::NoAdminAccounts);
+    }
+
+    for (index, admin_account) in admin_accounts.iter().enumerate() {
+        if admin_account.weight == 0 {
+            msg!("admin account {} has weight 0", admin_account.pubkey);
+            return Err(SlqError::InvalidAdminWeight);
+        }
+
+        if !admin_account.roles.is_valid() {
+            msg!("admin account {} has unknown roles", admin_account.pubkey);
+            return Err(SlqError::InvalidRoles);
+        }
+
+        if admin_accounts[..index]
+            .iter()
+            .any(|other| other.pubkey == admin_account.pubkey)
+        {
+            msg!(
+                "admin account {} appears more than once",
+                admin_account.pubkey
+            );
+            return Err(SlqError::DuplicateAdminAccount);
+        }
+    }
+
+    Ok(())
+}
+
+/// Validates that the approvers can meet the approval threshold
+/// and every threshold of the approval policy.
+pub fn thresholds(admin_config: &AdminConfig) -> Result<(), SlqError> {
+    if admin_config.approval_threshold == 0 {
+        msg!("approval threshold must be greater than 0");
+        return Err(SlqError::InvalidApprovalThreshold);
+    }
+
+    let total_weight = admin_config.total_weight();
+    let highest_threshold = admin_config.highest_threshold();
+    if u32::from(highest_threshold) > total_weight {
+        msg!(
+            "approval threshold {} is above {}, the total approver weight",
+            highest_threshold,
+            total_weight
+        );
+        return Err(SlqError::InvalidApprovalThreshold);
+    }
+
+    Ok(())
+}
+
+/// Validates a recovery config.
+pub fn recovery_config(recovery: &RecoveryConfig) -> Result<(), SlqError> {
+    let recovery_accounts = &recovery.recovery_accounts;
+
+    if recovery_accounts.is_empty() || recovery_accounts.len() > MAX_ADMIN_ACCOUNTS {
+        msg!(
+            "{} recovery accounts, must be between 1 and {}",
+            recovery_accounts.len(),
+            MAX_ADMIN_ACCOUNTS
+        );
+        return Err(SlqError::InvalidRecoveryConfig);
+    }
+
+    for (index, account) in recovery_accounts.iter().enumerate() {
+        if recovery_accounts[..index].contains(account) {
+            msg!("recovery account {} appears more than once", account);
+            return Err(SlqError::InvalidRecoveryConfig);
+        }
+    }
+
+    if recovery.threshold == 0 || usize::from(recovery.threshold) > recovery_accounts.len() {
+        msg!(
+            "recovery threshold {} must be between 1 and {}, the number of recovery accounts",
+            recovery.threshold,
+            recovery_accounts.len()
+        );
+        return Err(SlqError::InvalidRecoveryConfig);
+    }
+
+    if recovery.inactivity_period <= 0 || recovery.timelock < 0 {
+        msg!(
+            "inactivity period {} must be positive and timelock {} must not be negative",
+            recovery.inactivity_period,
+            recovery.timelock
+        );
+        return Err(SlqError::InvalidRecoveryConfig);
+    }
+
+    Ok(())
+}
+
+/// Validates a guardian set, which may be empty.
+pub fn guardians(guardians: &[Pubkey]) -> Result<(), SlqError> {
+    if guardians.len() > MAX_GUARDIANS {
+        msg!(
+            "{} guardians, the maximum is {}",
+            guardians.len(),
+            MAX_GUARDIANS
+        );
+        return Err(SlqError::InvalidGuardians);
+    }
+
+    for (index, guardian) in guardians.iter().enumerate() {
+        if guardians[..index].contains(guardian) {
+            msg!("guardian {} appears more than once", guardian);
+            return Err(SlqError::InvalidGuardians);
+        }
+    }
+
+    Ok(())
+}
+
+/// Validates a spending limit.
+pub fn spending_limit(spending_limit: &SpendingLimit) -> Result<(), SlqError> {
+    if spending_limit.amount == 0 || spending_limit.period <= 0 {
+        msg!(
+            "spending limit amount {} and period {} must be positive",
+            spending_limit.amount,
+            spending_limit.period
+        );
+        return Err(SlqError::InvalidSpendingLimit);
+    }
+
+    Ok(())
+}
@@ -2,25 +2,40 @@
 
 use anyhow::{anyhow, bail, Result};
 use borsh::{BorshDeserialize, BorshSerialize};
-use solana_program::borsh::get_instance_packed_len;
+use solana_program::borsh1::get_instance_packed_len;
 use solana_program::instruction::{AccountMeta, Instruction};
 use solana_program::sysvar::Sysvar;
 use solana_program::{
     account_info::{next_account_info, next_account_infos, AccountInfo},
+    clock::Clock,
     entrypoint::ProgramResult,
     msg,
-    program::invoke_signed,
+    program::{invoke, invoke_signed},
     program_error::ProgramError,
+    program_pack::Pack,
     pubkey::Pubkey,
     rent::Rent,
-    system_instruction, system_program,
+    system_instruction, system_program, sysvar,
 };
+use spl_associated_token_account::get_associated_token_address;
+use spl_associated_token_account::instruction::create_associated_token_account_idempotent;
+use spl_token::state::Account as TokenAccount;
 use std::convert::{TryFrom, TryInto};
 
-use crate::init::{create_admin_accounts_array, make_instance_pda, verify_pda};
+use crate::admin::{verify_admin_approval, verify_config_epoch};
+use crate::check;
+use crate::error::SlqError;
+use crate::init::{close_account, make_instance_pda, resize_account, verify_pda};
+use crate::state::AccountType;
 use crate::state::AdminConfig;
+use crate::state::ApprovalAction;
+use crate::state::Roles;
+use crate::state::SlqAccount;
 use crate::state::SlqInstance;
+use crate::state::SpendingLimit;
+use crate::state::Vault;
 use crate::state::MAX_ADMIN_ACCOUNTS;
+use crate::validate;
 use crate::SlqInstruction;
 
 pub fn exec(
@@ -32,6 +47,12 @@ pub fn exec(
         SlqVaultInstruction::CreateVault(instr) => instr.exec(program_id, accounts),
         SlqVaultInstruction::DepositToVault(instr) => instr.exec(program_id, accounts),
         SlqVaultInstruction::WithdrawFromVault(instr) => instr.exec(program_id, accounts),
+        SlqVaultInstruction::CreateVaultTokenAccount(instr) => instr.exec(program_id, accounts),
+        SlqVaultInstruction::DepositTokensToVault(instr) => instr.exec(program_id, accounts),
+        SlqVaultInstruction::WithdrawTokensFromVault(instr) => instr.exec(program_id, accounts),
+        SlqVaultInstruction::SetSpendingLimit(instr) => instr.exec(program_id, accounts),
+        SlqVaultInstruction::CloseLegacyVault(instr) => instr.exec(program_id, accounts),
+        SlqVaultInstruction::CloseVault(instr) => instr.exec(program_id, accounts),
     }
 }
 
@@ -40,66 +61,244 @@ pub enum SlqVaultInstruction {
     CreateVault(CreateVault),
     DepositToVault(DepositToVault),
     WithdrawFromVault(WithdrawFromVault),
+    CreateVaultTokenAccount(CreateVaultTokenAccount),
+    DepositTokensToVault(DepositTokensToVault),
+    WithdrawTokensFromVault(WithdrawTokensFromVault),
+    SetSpendingLimit(SetSpendingLimit),
+    CloseLegacyVault(CloseLegacyVault),
+    CloseVault(CloseVault),
 }
 
+impl SlqVaultInstruction {
+    /// The action to approve when the instance's authority invokes this instruction.
+    pub fn approval_action(
+        &self,
+        admin_config: &AdminConfig,
+        otherwise: ApprovalAction,
+    ) -> ApprovalAction {
+        match self {
+            SlqVaultInstruction::WithdrawFromVault(instr) => ApprovalAction::VaultWithdrawal {
+                amount: instr.amount,
+            },
+            SlqVaultInstruction::WithdrawTokensFromVault(_) => ApprovalAction::TokenWithdrawal,
+            SlqVaultInstruction::SetSpendingLimit(_) => {
+                SetSpendingLimit::approval_action(admin_config)
+            }
+            SlqVaultInstruction::CloseVault(_) => CloseVault::approval_action(admin_config),
+            SlqVaultInstruction::CreateVault(_)
+            | SlqVaultInstruction::DepositToVault(_)
+            | SlqVaultInstruction::CreateVaultTokenAccount(_)
+            | SlqVaultInstruction::DepositTokensToVault(_)
+            | SlqVaultInstruction::CloseLegacyVault(_) => otherwise,
+        }
+    }
+}
+
+/// Creates a rent-exempt vault governed by an instance, storing its `Vault` state.
+///
+/// Creating a vault needs admin approval, as the instance
+/// can't be destroyed while it has vaults.
+///
 /// # Accounts
 ///
-/// - 0: payer: signer, writable
-/// - 1: vault: pda, writable, owner=program_id
-/// - 2: system_program: executable
+/// - 0: payer - writable, signer
+/// - 1: instance_pda - pda, writable
+/// - 2: vault - pda, writable, uninitialized
+/// - 3: system_program - executable
+/// - 4..: admin_accounts - signer, distinct admins whose weights meet the approval threshold
 #[derive(BorshSerialize, BorshDeserialize, Debug)]
 pub struct CreateVault {
+    pub instance_name: String,
     pub vault_name: String,
+    pub instance_pda_bump_seed: u8,
     pub vault_bump_seed: u8,
 }
 
+/// Moves lamports from `payer` into a vault.
+///
 /// # Accounts
 ///
-/// - 0: payer: signer, writable
-/// - 1: vault: pda, writable, owner=program_id
-/// - 2: system_program: executable
+/// - 0: payer - writable, signer
+/// - 1: vault - pda, writable, owner=program_id
+/// - 2: system_program - executable
 #[derive(BorshSerialize, BorshDeserialize, Debug)]
 pub struct DepositToVault {
-    pub vault_name: String,
-    pub vault_bump_seed: u8,
     pub amount: u64,
 }
 
+/// Moves lamports from a vault to any recipient.
+///
+/// The vault's instance must approve the withdrawal, either with admin
+/// signatures meeting the vault withdrawal threshold for `amount`
+/// or with its authority pda through an executed proposal.
+/// Within the vault's spending limit, a single admin approves on their own.
+/// The vault keeps at least its rent exemption,
+/// and nothing can be withdrawn while the instance is paused.
+///
 /// # Accounts
 ///
-/// - 0: payer: signer, writable
-/// - 1: vault: pda, writable, owner=program
-/// - 2: system_program: executable
+/// - 0: instance_pda - pda, the vault's instance
+/// - 1: vault - pda, writable, owner=program_id
+/// - 2: recipient - writable
+/// - 3..: admin_accounts - signer, distinct admins whose weights meet the withdrawal threshold,
+///   or a single admin within the spending limit
 #[derive(BorshSerialize, BorshDeserialize, Debug)]
 pub struct WithdrawFromVault {
-    pub vault_name: String,
-    pub vault_bump_seed: u8,
     pub amount: u64,
 }
 
+/// Creates the vault's associated token account for a mint,
+/// in which the vault holds that mint's tokens.
+///
+/// Creating an account that already exists succeeds without changing it,
+/// so lamports sent to its address beforehand can't block its creation.
+///
+/// # Accounts
+///
+/// - 0: payer - writable, signer
+/// - 1: vault - pda, owner=program_id
+/// - 2: vault_token_account - writable
+/// - 3: mint
+/// - 4: system_program - executable
+/// - 5: token_program - executable
+/// - 6: associated_token_program - executable
+/// - 7: rent_sysvar
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct CreateVaultTokenAccount {}
+
+/// Moves SPL tokens from a token account of `depositor` into the vault.
+///
+/// # Accounts
+///
+/// - 0: depositor - signer, owner of source_token_account
+/// - 1: source_token_account - writable
+/// - 2: vault - pda, owner=program_id
+/// - 3: vault_token_account - writable, the vault's associated token account
+/// - 4: token_program - executable
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct DepositTokensToVault {
+    /// In the mint's smallest unit.
+    pub amount: u64,
+}
+
+/// Moves SPL tokens from the vault to any token account of the same mint.
+///
+/// Approval works as for `WithdrawFromVault`,
+/// with the threshold of `ApprovalAction::TokenWithdrawal`.
+///
+/// # Accounts
+///
+/// - 0: instance_pda - pda, the vault's instance
+/// - 1: vault - pda, writable, owner=program_id
+/// - 2: vault_token_account - writable, the vault's associated token account
+/// - 3: recipient_token_account - writable
+/// - 4: token_program - executable
+/// - 5..: admin_accounts - signer, distinct admins whose weights meet the withdrawal threshold,
+///   or a single admin within the spending limit for the mint
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct WithdrawTokensFromVault {
+    /// In the mint's smallest unit.
+    pub amount: u64,
+}
+
+/// Sets or removes the vault's spending limit for lamports or one mint.
+///
+/// A spending limit lets a single admin withdraw without meeting the
+/// withdrawal threshold, so setting one needs both the threshold-change
+/// threshold and that of an unlimited lamport withdrawal.
+/// Changing an existing limit keeps what was spent in the current period.
+/// Spending limits are ignored while the instance has an execution delay.
+///
+/// The vault account grows or shrinks,
+/// with the difference in rent paid by or refunded to `rent_payer`.
+///
+/// # Accounts
+///
+/// - 0: rent_payer - writable, signer
+/// - 1: instance_pda - pda, writable, the vault's instance
+/// - 2: vault - pda, writable, owner=program_id
+/// - 3: system_program - executable
+/// - 4..: admin_accounts - signer, distinct admins whose weights meet the threshold
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct SetSpendingLimit {
+    pub expected_config_epoch: u64,
+    /// The mint of the tokens limited, `None` for lamports.
+    pub mint: Option<Pubkey>,
+    /// In lamports, or the mint's smallest unit. 0 removes the limit.
+    pub amount: u64,
+    /// In seconds.
+    pub period: i64,
+}
+
+/// Closes a vault of the instance, sending all its lamports to `recipient`,
+/// so that the instance can be destroyed once it has no vaults.
+///
+/// The vault's token accounts passed must be empty, and are closed along with it.
+/// Token accounts that aren't passed stay at the vault's address,
+/// and a vault created again under the same name controls them.
+///
+/// # Accounts
+///
+/// - 0: instance_pda - pda, writable, the vault's instance
+/// - 1: vault - pda, writable, owner=program_id
+/// - 2: recipient - writable
+/// - 3: token_program - executable
+/// - 4..4+token_accounts: vault_token_accounts - writable, the vault's associated token accounts
+/// - 4+token_accounts..: admin_accounts - signer, distinct admins whose weights meet the threshold
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct CloseVault {
+    pub expected_config_epoch: u64,
+    pub token_accounts: u8,
+}
+
+/// Closes a vault derived from its creator, from before instances governed vaults,
+/// sending all its lamports to the creator.
+///
+/// Such vaults stored no data and have no instance to approve withdrawals,
+/// so the creator that could withdraw from them before is the only one that can close them.
+///
+/// # Accounts
+///
+/// - 0: creator - writable, signer
+/// - 1: vault - pda, writable, owner=program_id
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct CloseLegacyVault {
+    vault_name: String,
+    vault_bump_seed: u8,
+}
+
 impl CreateVault {
     pub fn build_instruction(
         program_id: &Pubkey,
         payer: &Pubkey,
+        admin_pubkeys: &[Pubkey],
+        instance_name: &str,
         vault_name: &str,
     ) -> Result<Instruction> {
-        let (vault_pubkey, vault_bump_seed) = vault_pda(program_id, payer, vault_name);
+        let (instance_pda, instance_pda_bump_seed) = make_instance_pda(program_id, instance_name);
+        let (vault_pubkey, vault_bump_seed) = make_vault_pda(program_id, &instance_pda, vault_name);
 
         let slq_instruction =
             SlqInstruction::Vault(SlqVaultInstruction::CreateVault(CreateVault {
+                instance_name: instance_name.to_string(),
                 vault_name: vault_name.to_string(),
+                instance_pda_bump_seed,
                 vault_bump_seed,
             }));
-        //        let mut slq_data: Vec<u8> = Vec::new();
-        //        slq_instruction.serialize(&mut slq_data)
-        //            .map_err(|_| anyhow!("unable to serialize instruction"))?;
 
-        let accounts = vec![
+        let mut accounts = vec![
             AccountMeta::new(*payer, true),
+            AccountMeta::new(instance_pda, false),
             AccountMeta::new(vault_pubkey, false),
             AccountMeta::new_readonly(system_program::ID, false),
         ];
 
+        accounts.extend(
+            admin_pubkeys
+                .iter()
+                .map(|admin_pubkey| AccountMeta::new_readonly(*admin_pubkey, true)),
+        );
+
         Ok(Instruction::new_with_borsh(
             *program_id,
             &slq_instruction,
@@ -110,29 +309,90 @@ impl CreateVault {
     fn exec(&self, program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
         let accounts_iter = &mut accounts.iter();
         let payer = next_account_info(accounts_iter)?;
+        let instance_pda = next_account_info(accounts_iter)?;
         let vault = next_account_info(accounts_iter)?;
+        let system_program = next_account_info(accounts_iter)?;
+
+        {
+            check::signer(payer, "payer")?;
+            check::writable(payer, "payer")?;
+            check::writable(instance_pda, "instance_pda")?;
+            check::owner(instance_pda, program_id, "instance_pda")?;
+            check::writable(vault, "vault")?;
+            check::uninitialized(vault, "vault")?;
+            check::system_program(system_program)?;
+
+            verify_pda(
+                program_id,
+                &self.instance_name,
+                instance_pda.key,
+                self.instance_pda_bump_seed,
+                make_instance_pda,
+            )?;
+            verify_pda(
+                program_id,
+                &self.vault_name,
+                vault.key,
+                self.vault_bump_seed,
+                |program_id, vault_name| make_vault_pda(program_id, instance_pda.key, vault_name),
+            )?;
+        }
 
-        assert!(payer.is_signer);
-        assert!(payer.is_writable);
-        // todo vault asserts
+        let mut instance = SlqInstance::load(&instance_pda.data.borrow())?;
+
+        verify_admin_approval(
+            program_id,
+            instance_pda.key,
+            &instance.admin_config,
+            ApprovalAction::Default,
+            accounts_iter.as_slice(),
+        )?;
 
-        let (vault_, vault_bump_seed_) = vault_pda(program_id, payer.key, &self.vault_name);
-        assert_eq!(vault.key, &vault_);
-        assert_eq!(self.vault_bump_seed, vault_bump_seed_);
+        let vault_state = Vault {
+            creator: *payer.key,
+            name: self.vault_name.clone(),
+            bump_seed: self.vault_bump_seed,
+            instance: *instance_pda.key,
+            created_slot: Clock::get()?.slot,
+            spending_limits: Vec::new(),
+        };
 
-        let lamports = 1000; // todo
+        let vault_size = vault_state.packed_len()?;
+        let rent = Rent::get()?;
+        let rent_lamports = rent.minimum_balance(vault_size);
 
+        if payer.lamports() < rent_lamports {
+            msg!("payer does not have enough lamports to pay vault rent");
+            return Err(SlqError::InsufficientFunds.into());
+        }
+
+        let space = u64::try_from(vault_size).map_err(|_| SlqError::ArithmeticOverflow)?;
         invoke_signed(
-            &system_instruction::create_account(payer.key, vault.key, lamports, 0, program_id),
+            &system_instruction::create_account(
+                payer.key,
+                vault.key,
+                rent_lamports,
+                space,
+                program_id,
+            ),
             &[payer.clone(), vault.clone()],
             &[&[
                 b"vault",
+                instance_pda.key.as_ref(),
                 self.vault_name.as_ref(),
-                payer.key.as_ref(),
                 &[self.vault_bump_seed],
             ]],
         )?;
 
+        vault_state.store(&mut vault.data.borrow_mut())?;
+
+        instance.vaults = instance
+            .vaults
+            .checked_add(1)
+            .ok_or(SlqError::ArithmeticOverflow)?;
+        instance.record_admin_action()?;
+        instance.store(&mut instance_pda.data.borrow_mut())?;
+
         Ok(())
     }
 }
@@ -141,15 +401,15 @@ impl DepositToVault {
     pub fn build_instruction(
         program_id: &Pubkey,
         payer: &Pubkey,
+        instance_name: &str,
         vault_name: &str,
         amount: u64,
     ) -> Result<Instruction> {
-        let (vault_pubkey, vault_bump_seed) = vault_pda(program_id, payer, vault_name);
+        let (instance_pda, _) = make_instance_pda(program_id, instance_name);
+        let (vault_pubkey, _) = make_vault_pda(program_id, &instance_pda, vault_name);
 
         let slq_instruction =
             SlqInstruction::Vault(SlqVaultInstruction::DepositToVault(DepositToVault {
-                vault_name: vault_name.to_string(),
-                vault_bump_seed,
                 amount,
             }));
 
@@ -170,18 +430,22 @@ impl DepositToVault {
         let accounts_iter = &mut accounts.iter();
         let payer = next_account_info(accounts_iter)?;
         let vault = next_account_info(accounts_iter)?;
+        let system_program = next_account_info(accounts_iter)?;
 
-        // todo validation
+        {
+            check::signer(payer, "payer")?;
+            check::writable(payer, "payer")?;
+            check::writable(vault, "vault")?;
+            check::owner(vault, program_id, "vault")?;
+            check::system_program(system_program)?;
+        }
 
-        invoke_signed(
+        // Only vaults hold lamports for their instance.
+        Vault::load(&vault.data.borrow())?;
+
+        invoke(
             &system_instruction::transfer(payer.key, vault.key, self.amount),
             &[payer.clone(), vault.clone()],
-            &[&[
-                b"vault",
-                self.vault_name.as_ref(),
-                payer.key.as_ref(),
-                &[self.vault_bump_seed],
-            ]],
         )?;
 
         Ok(())
@@ -191,23 +455,117 @@ impl DepositToVault {
 impl WithdrawFromVault {
     pub fn build_instruction(
         program_id: &Pubkey,
-        payer: &Pubkey,
+        admin_pubkeys: &[Pubkey],
+        instance_name: &str,
         vault_name: &str,
+        recipient: &Pubkey,
         amount: u64,
     ) -> Result<Instruction> {
-        let (vault_pubkey, vault_bump_seed) = vault_pda(program_id, payer, vault_name);
+        let (instance_pda, _) = make_instance_pda(program_id, instance_name);
+        let (vault_pubkey, _) = make_vault_pda(program_id, &instance_pda, vault_name);
 
         let slq_instruction =
             SlqInstruction::Vault(SlqVaultInstruction::WithdrawFromVault(WithdrawFromVault {
-                vault_name: vault_name.to_string(),
-                vault_bump_seed,
                 amount,
             }));
 
+        let mut accounts = vec![
+            AccountMeta::new_readonly(instance_pda, false),
+            AccountMeta::new(vault_pubkey, false),
+            AccountMeta::new(*recipient, false),
+        ];
+
+        accounts.extend(
+            admin_pubkeys
+                .iter()
+                .map(|admin_pubkey| AccountMeta::new_readonly(*admin_pubkey, true)),
+        );
+
+        Ok(Instruction::new_with_borsh(
+            *program_id,
+            &slq_instruction,
+            accounts,
+        ))
+    }
+
+    fn exec(&self, program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+        let instance_pda = next_account_info(accounts_iter)?;
+        let vault = next_account_info(accounts_iter)?;
+        let recipient = next_account_info(accounts_iter)?;
+
+        {
+            check::owner(instance_pda, program_id, "instance_pda")?;
+            check::writable(vault, "vault")?;
+            check::owner(vault, program_id, "vault")?;
+            check::writable(recipient, "recipient")?;
+        }
+
+        let mut vault_state = load_vault(vault, instance_pda)?;
+
+        let instance = SlqInstance::load(&instance_pda.data.borrow())?;
+        instance.verify_not_paused()?;
+
+        verify_withdrawal_approval(
+            program_id,
+            instance_pda.key,
+            &instance,
+            &mut vault_state,
+            None,
+            self.amount,
+            accounts_iter.as_slice(),
+        )?;
+        vault_state.store(&mut vault.data.borrow_mut())?;
+
+        let rent_lamports = Rent::get()?.minimum_balance(vault.data_len());
+        let available = vault.lamports().saturating_sub(rent_lamports);
+        if self.amount > available {
+            msg!(
+                "vault has {} lamports above its rent exemption, cannot withdraw {}",
+                available,
+                self.amount
+            );
+            return Err(SlqError::InsufficientFunds.into());
+        }
+
+        // The vault is owned by this program, so it is debited directly
+        // rather than through the system program.
+        **vault.lamports.borrow_mut() -= self.amount;
+        let recipient_lamports = recipient
+            .lamports()
+            .checked_add(self.amount)
+            .ok_or(SlqError::ArithmeticOverflow)?;
+        **recipient.lamports.borrow_mut() = recipient_lamports;
+
+        Ok(())
+    }
+}
+
+impl CreateVaultTokenAccount {
+    pub fn build_instruction(
+        program_id: &Pubkey,
+        payer: &Pubkey,
+        instance_name: &str,
+        vault_name: &str,
+        mint: &Pubkey,
+    ) -> Result<Instruction> {
+        let (instance_pda, _) = make_instance_pda(program_id, instance_name);
+        let (vault_pubkey, _) = make_vault_pda(program_id, &instance_pda, vault_name);
+        let vault_token_account = get_associated_token_address(&vault_pubkey, mint);
+
+        let slq_instruction = SlqInstruction::Vault(SlqVaultInstruction::CreateVaultTokenAccount(
+            CreateVaultTokenAccount {},
+        ));
+
         let accounts = vec![
             AccountMeta::new(*payer, true),
-            AccountMeta::new(vault_pubkey, false),
+            AccountMeta::new_readonly(vault_pubkey, false),
+            AccountMeta::new(vault_token_account, false),
+            AccountMeta::new_readonly(*mint, false),
             AccountMeta::new_readonly(system_program::ID, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(spl_associated_token_account::id(), false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
         ];
 
         Ok(Instruction::new_with_borsh(
@@ -221,29 +579,671 @@ impl WithdrawFromVault {
         let accounts_iter = &mut accounts.iter();
         let payer = next_account_info(accounts_iter)?;
         let vault = next_account_info(accounts_iter)?;
+        let vault_token_account = next_account_info(accounts_iter)?;
+        let mint = next_account_info(accounts_iter)?;
+        let system_program = next_account_info(accounts_iter)?;
+        let token_program = next_account_info(accounts_iter)?;
+        let associated_token_program = next_account_info(accounts_iter)?;
+        let rent_sysvar = next_account_info(accounts_iter)?;
+
+        {
+            check::signer(payer, "payer")?;
+            check::writable(payer, "payer")?;
+            check::owner(vault, program_id, "vault")?;
+            check::writable(vault_token_account, "vault_token_account")?;
+            check::system_program(system_program)?;
+            check::token_program(token_program)?;
+            check::associated_token_program(associated_token_program)?;
+        }
+
+        Vault::load(&vault.data.borrow())?;
+
+        let expected_token_account = get_associated_token_address(vault.key, mint.key);
+        if *vault_token_account.key != expected_token_account {
+            msg!(
+                "expected the vault's token account {}, got {}",
+                expected_token_account,
+                vault_token_account.key
+            );
+            return Err(SlqError::WrongTokenAccount.into());
+        }
+
+        invoke(
+            &create_associated_token_account_idempotent(
+                payer.key,
+                vault.key,
+                mint.key,
+                &spl_token::id(),
+            ),
+            &[
+                payer.clone(),
+                vault_token_account.clone(),
+                vault.clone(),
+                mint.clone(),
+                system_program.clone(),
+                token_program.clone(),
+                rent_sysvar.clone(),
+                associated_token_program.clone(),
+            ],
+        )
+    }
+}
+
+impl DepositTokensToVault {
+    pub fn build_instruction(
+        program_id: &Pubkey,
+        depositor: &Pubkey,
+        source_token_account: &Pubkey,
+        instance_name: &str,
+        vault_name: &str,
+        mint: &Pubkey,
+        amount: u64,
+    ) -> Result<Instruction> {
+        let (instance_pda, _) = make_instance_pda(program_id, instance_name);
+        let (vault_pubkey, _) = make_vault_pda(program_id, &instance_pda, vault_name);
+        let vault_token_account = get_associated_token_address(&vault_pubkey, mint);
+
+        let slq_instruction = SlqInstruction::Vault(SlqVaultInstruction::DepositTokensToVault(
+            DepositTokensToVault { amount },
+        ));
+
+        let accounts = vec![
+            AccountMeta::new_readonly(*depositor, true),
+            AccountMeta::new(*source_token_account, false),
+            AccountMeta::new_readonly(vault_pubkey, false),
+            AccountMeta::new(vault_token_account, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ];
+
+        Ok(Instruction::new_with_borsh(
+            *program_id,
+            &slq_instruction,
+            accounts,
+        ))
+    }
+
+    fn exec(&self, program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+        let depositor = next_account_info(accounts_iter)?;
+        let source_token_account = next_account_info(accounts_iter)?;
+        let vault = next_account_info(accounts_iter)?;
+        let vault_token_account = next_account_info(accounts_iter)?;
+        let token_program = next_account_info(accounts_iter)?;
+
+        {
+            check::signer(depositor, "depositor")?;
+            check::writable(source_token_account, "source_token_account")?;
+            check::owner(vault, program_id, "vault")?;
+            check::writable(vault_token_account, "vault_token_account")?;
+            check::token_program(token_program)?;
+        }
+
+        Vault::load(&vault.data.borrow())?;
+        load_vault_token_account(vault, vault_token_account)?;
+
+        invoke(
+            &spl_token::instruction::transfer(
+                token_program.key,
+                source_token_account.key,
+                vault_token_account.key,
+                depositor.key,
+                &[],
+                self.amount,
+            )?,
+            &[
+                source_token_account.clone(),
+                vault_token_account.clone(),
+                depositor.clone(),
+                token_program.clone(),
+            ],
+        )
+    }
+}
+
+impl WithdrawTokensFromVault {
+    pub fn build_instruction(
+        program_id: &Pubkey,
+        admin_pubkeys: &[Pubkey],
+        instance_name: &str,
+        vault_name: &str,
+        mint: &Pubkey,
+        recipient_token_account: &Pubkey,
+        amount: u64,
+    ) -> Result<Instruction> {
+        let (instance_pda, _) = make_instance_pda(program_id, instance_name);
+        let (vault_pubkey, _) = make_vault_pda(program_id, &instance_pda, vault_name);
+        let vault_token_account = get_associated_token_address(&vault_pubkey, mint);
+
+        let slq_instruction = SlqInstruction::Vault(SlqVaultInstruction::WithdrawTokensFromVault(
+            WithdrawTokensFromVault { amount },
+        ));
+
+        let mut accounts = vec![
+            AccountMeta::new_readonly(instance_pda, false),
+            AccountMeta::new(vault_pubkey, false),
+            AccountMeta::new(vault_token_account, false),
+            AccountMeta::new(*recipient_token_account, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ];
+
+        accounts.extend(
+            admin_pubkeys
+                .iter()
+                .map(|admin_pubkey| AccountMeta::new_readonly(*admin_pubkey, true)),
+        );
+
+        Ok(Instruction::new_with_borsh(
+            *program_id,
+            &slq_instruction,
+            accounts,
+        ))
+    }
+
+    fn exec(&self, program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+        let instance_pda = next_account_info(accounts_iter)?;
+        let vault = next_account_info(accounts_iter)?;
+        let vault_token_account = next_account_info(accounts_iter)?;
+        let recipient_token_account = next_account_info(accounts_iter)?;
+        let token_program = next_account_info(accounts_iter)?;
+
+        {
+            check::owner(instance_pda, program_id, "instance_pda")?;
+            check::writable(vault, "vault")?;
+            check::owner(vault, program_id, "vault")?;
+            check::writable(vault_token_account, "vault_token_account")?;
+            check::writable(recipient_token_account, "recipient_token_account")?;
+            check::token_program(token_program)?;
+        }
+
+        let mut vault_state = load_vault(vault, instance_pda)?;
+        let token_account = load_vault_token_account(vault, vault_token_account)?;
+
+        let instance = SlqInstance::load(&instance_pda.data.borrow())?;
+        instance.verify_not_paused()?;
+
+        verify_withdrawal_approval(
+            program_id,
+            instance_pda.key,
+            &instance,
+            &mut vault_state,
+            Some(token_account.mint),
+            self.amount,
+            accounts_iter.as_slice(),
+        )?;
+        vault_state.store(&mut vault.data.borrow_mut())?;
 
-        // todo validation
-        // todo:
-        // - if pda_vault has enough balance
-        // - if the caller is the one who created the vault
         invoke_signed(
-            &system_instruction::transfer(vault.key, payer.key, self.amount),
-            &[payer.clone(), vault.clone()],
+            &spl_token::instruction::transfer(
+                token_program.key,
+                vault_token_account.key,
+                recipient_token_account.key,
+                vault.key,
+                &[],
+                self.amount,
+            )?,
+            &[
+                vault_token_account.clone(),
+                recipient_token_account.clone(),
+                vault.clone(),
+                token_program.clone(),
+            ],
             &[&[
                 b"vault",
-                self.vault_name.as_ref(),
-                payer.key.as_ref(),
-                &[self.vault_bump_seed],
+                instance_pda.key.as_ref(),
+                vault_state.name.as_ref(),
+                &[vault_state.bump_seed],
             ]],
+        )
+    }
+}
+
+impl SetSpendingLimit {
+    #[allow(clippy::too_many_arguments)]
+    pub fn build_instruction(
+        program_id: &Pubkey,
+        rent_payer: &Pubkey,
+        admin_pubkeys: &[Pubkey],
+        instance_name: &str,
+        vault_name: &str,
+        expected_config_epoch: u64,
+        mint: Option<Pubkey>,
+        amount: u64,
+        period: i64,
+    ) -> Result<Instruction> {
+        let (instance_pda, _) = make_instance_pda(program_id, instance_name);
+        let (vault_pubkey, _) = make_vault_pda(program_id, &instance_pda, vault_name);
+
+        let slq_instruction =
+            SlqInstruction::Vault(SlqVaultInstruction::SetSpendingLimit(SetSpendingLimit {
+                expected_config_epoch,
+                mint,
+                amount,
+                period,
+            }));
+
+        let mut accounts = vec![
+            AccountMeta::new(*rent_payer, true),
+            AccountMeta::new(instance_pda, false),
+            AccountMeta::new(vault_pubkey, false),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ];
+
+        accounts.extend(
+            admin_pubkeys
+                .iter()
+                .map(|admin_pubkey| AccountMeta::new_readonly(*admin_pubkey, true)),
+        );
+
+        Ok(Instruction::new_with_borsh(
+            *program_id,
+            &slq_instruction,
+            accounts,
+        ))
+    }
+
+    /// The stricter of changing thresholds and an unlimited lamport withdrawal.
+    pub fn approval_action(admin_config: &AdminConfig) -> ApprovalAction {
+        admin_config.stricter(
+            ApprovalAction::ThresholdChange,
+            ApprovalAction::VaultWithdrawal { amount: u64::MAX },
+        )
+    }
+
+    fn exec(&self, program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+        let rent_payer = next_account_info(accounts_iter)?;
+        let instance_pda = next_account_info(accounts_iter)?;
+        let vault = next_account_info(accounts_iter)?;
+        let system_program = next_account_info(accounts_iter)?;
+
+        {
+            check::writable(rent_payer, "rent_payer")?;
+            check::signer(rent_payer, "rent_payer")?;
+            check::writable(instance_pda, "instance_pda")?;
+            check::owner(instance_pda, program_id, "instance_pda")?;
+            check::writable(vault, "vault")?;
+            check::owner(vault, program_id, "vault")?;
+            check::system_program(system_program)?;
+        }
+
+        let mut vault_state = load_vault(vault, instance_pda)?;
+        let mut instance = SlqInstance::load(&instance_pda.data.borrow())?;
+        verify_config_epoch(&instance, self.expected_config_epoch)?;
+
+        verify_admin_approval(
+            program_id,
+            instance_pda.key,
+            &instance.admin_config,
+            Self::approval_action(&instance.admin_config),
+            accounts_iter.as_slice(),
         )?;
 
+        if self.amount == 0 {
+            let len = vault_state.spending_limits.len();
+            vault_state
+                .spending_limits
+                .retain(|spending_limit| spending_limit.mint != self.mint);
+            if vault_state.spending_limits.len() == len {
+                msg!("vault has no spending limit for {:?}", self.mint);
+                return Err(SlqError::InvalidSpendingLimit.into());
+            }
+        } else {
+            let spending_limit = match vault_state.spending_limit_mut(self.mint) {
+                Some(spending_limit) => {
+                    spending_limit.amount = self.amount;
+                    spending_limit.period = self.period;
+                    *spending_limit
+                }
+                None => {
+                    let spending_limit = SpendingLimit {
+                        mint: self.mint,
+                        amount: self.amount,
+                        period: self.period,
+                        period_start: Clock::get()?.unix_timestamp,
+                        spent: 0,
+                    };
+                    vault_state.spending_limits.push(spending_limit);
+                    spending_limit
+                }
+            };
+            validate::spending_limit(&spending_limit)?;
+        }
+
+        // The vault's lamports beyond its rent exemption can be withdrawn,
+        // so `rent_payer` pays for the vault growing rather than the vault itself.
+        let new_len = vault_state.packed_len()?;
+        let rent = Rent::get()?;
+        let extra_rent = rent
+            .minimum_balance(new_len)
+            .saturating_sub(rent.minimum_balance(vault.data_len()));
+        if extra_rent > 0 {
+            invoke(
+                &system_instruction::transfer(rent_payer.key, vault.key, extra_rent),
+                &[rent_payer.clone(), vault.clone(), system_program.clone()],
+            )?;
+        }
+
+        resize_account(vault, rent_payer, system_program, new_len)?;
+        vault_state.store(&mut vault.data.borrow_mut())?;
+
+        instance.record_admin_action()?;
+        instance.store(&mut instance_pda.data.borrow_mut())?;
+
         Ok(())
     }
 }
 
-fn vault_pda(program_id: &Pubkey, payer: &Pubkey, name: &str) -> (Pubkey, u8) {
-    let vault_seeds = &[b"vault", name.as_bytes(), payer.as_ref()];
-    let (vault, vault_bump_seed) = Pubkey::find_program_address(vault_seeds, program_id);
+impl CloseVault {
+    pub fn build_instruction(
+        program_id: &Pubkey,
+        admin_pubkeys: &[Pubkey],
+        instance_name: &str,
+        vault_name: &str,
+        expected_config_epoch: u64,
+        recipient: &Pubkey,
+        mints: &[Pubkey],
+    ) -> Result<Instruction> {
+        let (instance_pda, _) = make_instance_pda(program_id, instance_name);
+        let (vault_pubkey, _) = make_vault_pda(program_id, &instance_pda, vault_name);
+
+        let slq_instruction = SlqInstruction::Vault(SlqVaultInstruction::CloseVault(CloseVault {
+            expected_config_epoch,
+            token_accounts: mints.len().try_into()?,
+        }));
+
+        let mut accounts = vec![
+            AccountMeta::new(instance_pda, false),
+            AccountMeta::new(vault_pubkey, false),
+            AccountMeta::new(*recipient, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ];
+
+        accounts.extend(mints.iter().map(|mint| {
+            AccountMeta::new(get_associated_token_address(&vault_pubkey, mint), false)
+        }));
+
+        accounts.extend(
+            admin_pubkeys
+                .iter()
+                .map(|admin_pubkey| AccountMeta::new_readonly(*admin_pubkey, true)),
+        );
+
+        Ok(Instruction::new_with_borsh(
+            *program_id,
+            &slq_instruction,
+            accounts,
+        ))
+    }
+
+    /// The stricter of creating a vault and an unlimited lamport withdrawal.
+    pub fn approval_action(admin_config: &AdminConfig) -> ApprovalAction {
+        admin_config.stricter(
+            ApprovalAction::Default,
+            ApprovalAction::VaultWithdrawal { amount: u64::MAX },
+        )
+    }
+
+    fn exec(&self, program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+        let instance_pda = next_account_info(accounts_iter)?;
+        let vault = next_account_info(accounts_iter)?;
+        let recipient = next_account_info(accounts_iter)?;
+        let token_program = next_account_info(accounts_iter)?;
+        let vault_token_accounts = next_account_infos(accounts_iter, self.token_accounts.into())?;
+
+        {
+            check::writable(instance_pda, "instance_pda")?;
+            check::owner(instance_pda, program_id, "instance_pda")?;
+            check::writable(vault, "vault")?;
+            check::owner(vault, program_id, "vault")?;
+            check::writable(recipient, "recipient")?;
+            check::token_program(token_program)?;
+            for vault_token_account in vault_token_accounts {
+                check::writable(vault_token_account, "vault_token_account")?;
+            }
+        }
+
+        let vault_state = load_vault(vault, instance_pda)?;
+        let mut instance = SlqInstance::load(&instance_pda.data.borrow())?;
+        instance.verify_not_paused()?;
+        verify_config_epoch(&instance, self.expected_config_epoch)?;
+
+        verify_admin_approval(
+            program_id,
+            instance_pda.key,
+            &instance.admin_config,
+            Self::approval_action(&instance.admin_config),
+            accounts_iter.as_slice(),
+        )?;
+
+        for vault_token_account in vault_token_accounts {
+            let token_account = load_vault_token_account(vault, vault_token_account)?;
+            if token_account.amount > 0 {
+                msg!(
+                    "vault token account {} holds {} of mint {}",
+                    vault_token_account.key,
+                    token_account.amount,
+                    token_account.mint
+                );
+                return Err(SlqError::VaultNotEmpty.into());
+            }
+
+            invoke_signed(
+                &spl_token::instruction::close_account(
+                    token_program.key,
+                    vault_token_account.key,
+                    recipient.key,
+                    vault.key,
+                    &[],
+                )?,
+                &[
+                    vault_token_account.clone(),
+                    recipient.clone(),
+                    vault.clone(),
+                    token_program.clone(),
+                ],
+                &[&[
+                    b"vault",
+                    instance_pda.key.as_ref(),
+                    vault_state.name.as_ref(),
+                    &[vault_state.bump_seed],
+                ]],
+            )?;
+        }
+
+        msg!(
+            "closing vault {}, {} lamports to {}",
+            vault_state.name,
+            vault.lamports(),
+            recipient.key
+        );
+        close_account(vault, recipient)?;
+
+        instance.vaults = instance
+            .vaults
+            .checked_sub(1)
+            .ok_or(SlqError::ArithmeticOverflow)?;
+        instance.record_admin_action()?;
+        instance.store(&mut instance_pda.data.borrow_mut())?;
+
+        Ok(())
+    }
+}
+
+impl CloseLegacyVault {
+    pub fn build_instruction(
+        program_id: &Pubkey,
+        creator: &Pubkey,
+        vault_name: &str,
+    ) -> Result<Instruction> {
+        let (vault_pubkey, vault_bump_seed) =
+            make_legacy_vault_pda(program_id, creator, vault_name);
+
+        let slq_instruction =
+            SlqInstruction::Vault(SlqVaultInstruction::CloseLegacyVault(CloseLegacyVault {
+                vault_name: vault_name.to_string(),
+                vault_bump_seed,
+            }));
+
+        let accounts = vec![
+            AccountMeta::new(*creator, true),
+            AccountMeta::new(vault_pubkey, false),
+        ];
+
+        Ok(Instruction::new_with_borsh(
+            *program_id,
+            &slq_instruction,
+            accounts,
+        ))
+    }
+
+    fn exec(&self, program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+        let creator = next_account_info(accounts_iter)?;
+        let vault = next_account_info(accounts_iter)?;
+
+        {
+            check::signer(creator, "creator")?;
+            check::writable(creator, "creator")?;
+            check::writable(vault, "vault")?;
+            check::owner(vault, program_id, "vault")?;
+
+            verify_pda(
+                program_id,
+                &self.vault_name,
+                vault.key,
+                self.vault_bump_seed,
+                |program_id, vault_name| make_legacy_vault_pda(program_id, creator.key, vault_name),
+            )?;
+        }
+
+        // Their pda alone names the creator.
+        if vault.data_len() > 0 {
+            msg!(
+                "vault {} stores data, it is not derived from its creator",
+                vault.key
+            );
+            return Err(SlqError::WrongAccountType.into());
+        }
+
+        msg!(
+            "closing vault {}, {} lamports to {}",
+            self.vault_name,
+            vault.lamports(),
+            creator.key
+        );
+        close_account(vault, creator)
+    }
+}
+
+/// Loads the vault's state, verifying that it belongs to `instance_pda`.
+fn load_vault(vault: &AccountInfo, instance_pda: &AccountInfo) -> Result<Vault, ProgramError> {
+    let vault_state = Vault::load(&vault.data.borrow())?;
+    if vault_state.instance != *instance_pda.key {
+        msg!(
+            "vault belongs to instance {}, got {}",
+            vault_state.instance,
+            instance_pda.key
+        );
+        return Err(SlqError::WrongInstance.into());
+    }
+
+    Ok(vault_state)
+}
+
+/// Verifies that the instance approves withdrawing `amount` of `mint`,
+/// or of lamports if `None`.
+///
+/// A single approver within the vault's spending limit approves on their own,
+/// which is recorded against the limit. Otherwise `verify_admin_approval` applies.
+/// Spending limits don't apply while the instance has an execution delay,
+/// as a single approver would otherwise withdraw without waiting it out.
+fn verify_withdrawal_approval(
+    program_id: &Pubkey,
+    instance_pda: &Pubkey,
+    instance: &SlqInstance,
+    vault_state: &mut Vault,
+    mint: Option<Pubkey>,
+    amount: u64,
+    admin_signers: &[AccountInfo],
+) -> ProgramResult {
+    let admin_config = &instance.admin_config;
+
+    let single_signer = match admin_signers {
+        [admin_signer] if admin_config.policy.execution_delay == 0 => Some(admin_signer),
+        _ => None,
+    };
+
+    if let Some(admin_signer) = single_signer {
+        let is_approver = admin_signer.is_signer
+            && admin_config
+                .signer_position(program_id, admin_signer.key)
+                .is_some_and(|index| admin_config.admin_accounts[index].has_role(Roles::APPROVER));
+
+        if let Some(spending_limit) = vault_state.spending_limit_mut(mint) {
+            if is_approver {
+                let now = Clock::get()?.unix_timestamp;
+                if spending_limit.try_spend(now, amount) {
+                    return Ok(());
+                }
+
+                msg!(
+                    "withdrawing {} would exceed the spending limit of {} per {} seconds, {} spent",
+                    amount,
+                    spending_limit.amount,
+                    spending_limit.period,
+                    spending_limit.spent
+                );
+            }
+        }
+    }
+
+    let action = match mint {
+        Some(_) => ApprovalAction::TokenWithdrawal,
+        None => ApprovalAction::VaultWithdrawal { amount },
+    };
+
+    verify_admin_approval(
+        program_id,
+        instance_pda,
+        admin_config,
+        action,
+        admin_signers,
+    )
+}
+
+/// Verifies that `vault_token_account` is the vault's associated token account
+/// for its mint, returning its state.
+fn load_vault_token_account(
+    vault: &AccountInfo,
+    vault_token_account: &AccountInfo,
+) -> Result<TokenAccount, ProgramError> {
+    check::owner(vault_token_account, &spl_token::id(), "vault_token_account")?;
+    let token_account = TokenAccount::unpack(&vault_token_account.data.borrow())?;
+
+    let expected_token_account = get_associated_token_address(vault.key, &token_account.mint);
+    if *vault_token_account.key != expected_token_account || token_account.owner != *vault.key {
+        msg!(
+            "expected the vault's token account {}, got {}",
+            expected_token_account,
+            vault_token_account.key
+        );
+        return Err(SlqError::WrongTokenAccount.into());
+    }
+
+    Ok(token_account)
+}
+
+/// A vault from before instances governed vaults, derived from its creator.
+pub fn make_legacy_vault_pda(program_id: &Pubkey, creator: &Pubkey, name: &str) -> (Pubkey, u8) {
+    let seeds = &[b"vault", name.as_bytes(), creator.as_ref()];
+    Pubkey::find_program_address(seeds, program_id)
+}
 
-    (vault, vault_bump_seed)
+/// A vault holding lamports, and SPL tokens in its associated token accounts,
+/// for `instance_pda`.
+pub fn make_vault_pda(program_id: &Pubkey, instance_pda: &Pubkey, name: &str) -> (Pubkey, u8) {
+    let seeds = &[b"vault", instance_pda.as_ref(), name.as_bytes()];
+    Pubkey::find_program_address(seeds, program_id)
 }
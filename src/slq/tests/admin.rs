@@ -0,0 +1,150 @@
+mod common;
+
+use solana_program::instruction::Instruction;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signer};
+
+use common::{assert_slq_error, TestInstance, INSTANCE_NAME};
+use slq::admin::{ChangeApprovalThresholdAdmin, DestroyInstanceAdmin, UnpauseAdmin};
+use slq::error::SlqError;
+
+fn change_approval_threshold(
+    test: &TestInstance,
+    admin_pubkeys: &[Pubkey],
+    expected_config_epoch: u64,
+    approval_threshold: u8,
+) -> Instruction {
+    ChangeApprovalThresholdAdmin::build_instruction(
+        &test.program_id,
+        &test.payer(),
+        admin_pubkeys,
+        INSTANCE_NAME.to_string(),
+        expected_config_epoch,
+        approval_threshold,
+    )
+    .unwrap()
+}
+
+#[tokio::test]
+async fn admin_instruction_applies_once_the_threshold_is_met() {
+    let mut test = TestInstance::start(3, 2).await;
+
+    let change = change_approval_threshold(&test, &test.admin_pubkeys(2), 0, 3);
+    test.process_with_admins(&[change], 2).await.unwrap();
+
+    let instance = test.instance().await;
+    assert_eq!(instance.admin_config.approval_threshold, 3);
+    assert_eq!(instance.config_epoch, 1);
+}
+
+#[tokio::test]
+async fn admin_instruction_below_the_threshold_is_refused() {
+    let mut test = TestInstance::start(3, 2).await;
+
+    let change = change_approval_threshold(&test, &test.admin_pubkeys(1), 0, 3);
+    let result = test.process_with_admins(&[change], 1).await;
+    assert_slq_error(result, SlqError::ApprovalThresholdNotMet);
+
+    assert_eq!(test.instance().await.admin_config.approval_threshold, 2);
+}
+
+#[tokio::test]
+async fn admin_signing_twice_counts_once() {
+    let mut test = TestInstance::start(3, 2).await;
+
+    let admin = test.admin_pubkeys(1)[0];
+    let change = change_approval_threshold(&test, &[admin, admin], 0, 3);
+    let result = test.process_with_admins(&[change], 1).await;
+    assert_slq_error(result, SlqError::DuplicateAdminSigner);
+}
+
+#[tokio::test]
+async fn signer_outside_the_admins_is_refused() {
+    let mut test = TestInstance::start(3, 2).await;
+
+    let outsider = Keypair::new();
+    let admin_pubkeys = [test.admin_pubkeys(1)[0], outsider.pubkey()];
+    let change = change_approval_threshold(&test, &admin_pubkeys, 0, 3);
+    let admin = test.admins[0].insecure_clone();
+    let result = test.process(&[change], &[&admin, &outsider]).await;
+    assert_slq_error(result, SlqError::NotAnAdmin);
+}
+
+#[tokio::test]
+async fn instruction_built_before_a_config_change_is_refused() {
+    let mut test = TestInstance::start(3, 2).await;
+
+    let first = change_approval_threshold(&test, &test.admin_pubkeys(2), 0, 3);
+    let second = change_approval_threshold(&test, &test.admin_pubkeys(2), 0, 1);
+    test.process_with_admins(&[first], 2).await.unwrap();
+
+    let result = test.process_with_admins(&[second], 2).await;
+    assert_slq_error(result, SlqError::ConfigChanged);
+}
+
+#[tokio::test]
+async fn unpause_built_before_a_config_change_is_refused() {
+    let mut test = TestInstance::start(3, 2).await;
+    let guardian = test.admins[0].insecure_clone();
+    test.pause(&guardian).await.unwrap();
+
+    let unpause = UnpauseAdmin::build_instruction(
+        &test.program_id,
+        &test.payer(),
+        &test.admin_pubkeys(3),
+        INSTANCE_NAME.to_string(),
+        0,
+    )
+    .unwrap();
+    test.set_execution_delay(0).await;
+
+    let result = test.process_with_admins(&[unpause], 3).await;
+    assert_slq_error(result, SlqError::ConfigChanged);
+    assert!(test.instance().await.paused);
+}
+
+fn destroy(test: &TestInstance, expected_config_epoch: u64) -> Instruction {
+    DestroyInstanceAdmin::build_instruction(
+        &test.program_id,
+        &test.payer(),
+        &test.admin_pubkeys(2),
+        INSTANCE_NAME.to_string(),
+        expected_config_epoch,
+        &Pubkey::new_unique(),
+    )
+    .unwrap()
+}
+
+#[tokio::test]
+async fn destroy_is_refused_while_the_authority_holds_lamports() {
+    let mut test = TestInstance::start(3, 2).await;
+    let authority_pda = test.authority_pda();
+    test.fund(&authority_pda, 1_000_000_000).await;
+
+    let destroy = destroy(&test, 0);
+    let result = test.process_with_admins(&[destroy], 2).await;
+    assert_slq_error(result, SlqError::InstanceInUse);
+    assert!(test.account_exists(&test.instance_pda()).await);
+}
+
+#[tokio::test]
+async fn destroy_built_before_a_config_change_is_refused() {
+    let mut test = TestInstance::start(3, 2).await;
+
+    let destroy = destroy(&test, 0);
+    test.set_execution_delay(0).await;
+
+    let result = test.process_with_admins(&[destroy], 2).await;
+    assert_slq_error(result, SlqError::ConfigChanged);
+}
+
+#[tokio::test]
+async fn execution_delay_requires_a_proposal() {
+    let mut test = TestInstance::start(3, 2).await;
+    test.set_execution_delay(100).await;
+
+    let config_epoch = test.config_epoch().await;
+    let change = change_approval_threshold(&test, &test.admin_pubkeys(3), config_epoch, 3);
+    let result = test.process_with_admins(&[change], 3).await;
+    assert_slq_error(result, SlqError::TimelockRequired);
+}
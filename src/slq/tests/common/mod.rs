@@ -0,0 +1,537 @@
+//! An slq instance running in `solana-program-test`, with helpers to drive it.
+
+#![allow(dead_code)]
+
+use solana_program::clock::Clock;
+use solana_program::instruction::{Instruction, InstructionError};
+use solana_program::program_pack::Pack;
+use solana_program::pubkey::Pubkey;
+use solana_program::system_instruction;
+use solana_program_test::{processor, BanksClientError, ProgramTest, ProgramTestContext};
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::transaction::{Transaction, TransactionError};
+use spl_associated_token_account::get_associated_token_address;
+use spl_associated_token_account::instruction::create_associated_token_account;
+use spl_token::state::{Account as TokenAccount, Mint};
+
+use slq::admin::{PauseAdmin, SetApprovalPolicyAdmin, SetGuardiansAdmin, SetRecoveryConfigAdmin};
+use slq::error::SlqError;
+use slq::init::{make_authority_pda, make_instance_pda, Init};
+use slq::proposal::{make_proposal_pda, Approve, CreateProposal, Execute};
+use slq::recovery::{CompleteRecovery, StartRecovery};
+use slq::state::{
+    AdminAccount, AdminConfig, AdminKind, ApprovalPolicy, Expiry, Proposal, RecoveryConfig, Roles,
+    SlqAccount, SlqInstance, Vault,
+};
+use slq::vault::{
+    make_vault_pda, CreateVault, CreateVaultTokenAccount, DepositToVault, DepositTokensToVault,
+    SetSpendingLimit,
+};
+
+pub const INSTANCE_NAME: &str = "test-instance";
+pub const VAULT_NAME: &str = "test-vault";
+
+pub struct TestInstance {
+    pub context: ProgramTestContext,
+    pub program_id: Pubkey,
+    /// Each has weight 1 and every role.
+    pub admins: Vec<Keypair>,
+}
+
+impl TestInstance {
+    /// Starts a test validator with an instance of `admin_count` admins
+    /// that needs `approval_threshold` of them.
+    pub async fn start(admin_count: usize, approval_threshold: u8) -> TestInstance {
+        let program_id = Pubkey::new_unique();
+        let program_test =
+            ProgramTest::new("slq", program_id, processor!(slq::process_instruction));
+        let context = program_test.start_with_context().await;
+
+        let admins: Vec<Keypair> = (0..admin_count).map(|_| Keypair::new()).collect();
+        let admin_accounts = admins
+            .iter()
+            .map(|admin| AdminAccount {
+                pubkey: admin.pubkey(),
+                weight: 1,
+                roles: Roles::ALL,
+                kind: AdminKind::Key,
+            })
+            .collect();
+
+        let mut test = TestInstance {
+            context,
+            program_id,
+            admins,
+        };
+
+        let payer = test.payer();
+        let init = Init::build_instruction(
+            &program_id,
+            &payer,
+            INSTANCE_NAME.to_string(),
+            approval_threshold,
+            8,
+            admin_accounts,
+        )
+        .unwrap();
+        test.process(&[init], &[]).await.unwrap();
+
+        test
+    }
+
+    pub fn payer(&self) -> Pubkey {
+        self.context.payer.pubkey()
+    }
+
+    pub fn instance_pda(&self) -> Pubkey {
+        make_instance_pda(&self.program_id, INSTANCE_NAME).0
+    }
+
+    pub fn authority_pda(&self) -> Pubkey {
+        make_authority_pda(&self.program_id, &self.instance_pda()).0
+    }
+
+    pub fn vault_pda(&self) -> Pubkey {
+        make_vault_pda(&self.program_id, &self.instance_pda(), VAULT_NAME).0
+    }
+
+    /// The pubkeys of the first `count` admins.
+    pub fn admin_pubkeys(&self, count: usize) -> Vec<Pubkey> {
+        self.admins[..count]
+            .iter()
+            .map(|admin| admin.pubkey())
+            .collect()
+    }
+
+    /// Sends `instructions` in one transaction paid for and signed by the payer,
+    /// and signed by `signers`.
+    pub async fn process(
+        &mut self,
+        instructions: &[Instruction],
+        signers: &[&Keypair],
+    ) -> Result<(), BanksClientError> {
+        // Identical transactions would otherwise share a signature.
+        let blockhash = self.context.get_new_latest_blockhash().await.unwrap();
+
+        let mut all_signers = vec![&self.context.payer];
+        all_signers.extend_from_slice(signers);
+
+        let transaction = Transaction::new_signed_with_payer(
+            instructions,
+            Some(&self.context.payer.pubkey()),
+            &all_signers,
+            blockhash,
+        );
+
+        self.context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+    }
+
+    /// Like `process`, with the first `count` admins also signing.
+    pub async fn process_with_admins(
+        &mut self,
+        instructions: &[Instruction],
+        count: usize,
+    ) -> Result<(), BanksClientError> {
+        let admins: Vec<Keypair> = self.admins[..count]
+            .iter()
+            .map(|admin| admin.insecure_clone())
+            .collect();
+        let signers: Vec<&Keypair> = admins.iter().collect();
+
+        self.process(instructions, &signers).await
+    }
+
+    /// Transfers `lamports` from the payer to `pubkey`.
+    pub async fn fund(&mut self, pubkey: &Pubkey, lamports: u64) {
+        let payer = self.payer();
+        let transfer = system_instruction::transfer(&payer, pubkey, lamports);
+        self.process(&[transfer], &[]).await.unwrap();
+    }
+
+    pub async fn account_data(&mut self, pubkey: &Pubkey) -> Vec<u8> {
+        self.context
+            .banks_client
+            .get_account(*pubkey)
+            .await
+            .unwrap()
+            .unwrap_or_else(|| panic!("account {} does not exist", pubkey))
+            .data
+    }
+
+    pub async fn account_exists(&mut self, pubkey: &Pubkey) -> bool {
+        self.context
+            .banks_client
+            .get_account(*pubkey)
+            .await
+            .unwrap()
+            .is_some()
+    }
+
+    pub async fn lamports(&mut self, pubkey: &Pubkey) -> u64 {
+        self.context
+            .banks_client
+            .get_balance(*pubkey)
+            .await
+            .unwrap()
+    }
+
+    pub async fn instance(&mut self) -> SlqInstance {
+        let instance_pda = self.instance_pda();
+        SlqInstance::load(&self.account_data(&instance_pda).await).unwrap()
+    }
+
+    pub async fn vault(&mut self) -> Vault {
+        let vault_pda = self.vault_pda();
+        Vault::load(&self.account_data(&vault_pda).await).unwrap()
+    }
+
+    pub async fn config_epoch(&mut self) -> u64 {
+        self.instance().await.config_epoch
+    }
+
+    pub async fn now(&mut self) -> i64 {
+        self.clock().await.unix_timestamp
+    }
+
+    async fn clock(&mut self) -> Clock {
+        self.context
+            .banks_client
+            .get_sysvar::<Clock>()
+            .await
+            .unwrap()
+    }
+
+    /// Moves the cluster's unix timestamp `seconds` forward.
+    pub async fn warp_forward(&mut self, seconds: i64) {
+        let mut clock = self.clock().await;
+        clock.unix_timestamp += seconds;
+        self.context.set_sysvar(&clock);
+    }
+
+    /// Sets the instance's execution delay with the approval of every admin.
+    pub async fn set_execution_delay(&mut self, execution_delay: i64) {
+        let config_epoch = self.config_epoch().await;
+        let policy = ApprovalPolicy {
+            execution_delay,
+            ..ApprovalPolicy::default()
+        };
+        let set_policy = SetApprovalPolicyAdmin::build_instruction(
+            &self.program_id,
+            &self.payer(),
+            &self.admin_pubkeys(self.admins.len()),
+            INSTANCE_NAME.to_string(),
+            config_epoch,
+            policy,
+        )
+        .unwrap();
+        self.process_with_admins(&[set_policy], self.admins.len())
+            .await
+            .unwrap();
+    }
+
+    /// Sets the instance's guardians with the approval of every admin.
+    pub async fn set_guardians(&mut self, guardians: Vec<Pubkey>) {
+        let config_epoch = self.config_epoch().await;
+        let set_guardians = SetGuardiansAdmin::build_instruction(
+            &self.program_id,
+            &self.payer(),
+            &self.admin_pubkeys(self.admins.len()),
+            INSTANCE_NAME.to_string(),
+            config_epoch,
+            guardians,
+        )
+        .unwrap();
+        self.process_with_admins(&[set_guardians], self.admins.len())
+            .await
+            .unwrap();
+    }
+
+    /// Pauses the instance, signed by `pauser`.
+    pub async fn pause(&mut self, pauser: &Keypair) -> Result<(), BanksClientError> {
+        let pause = PauseAdmin::build_instruction(
+            &self.program_id,
+            &pauser.pubkey(),
+            INSTANCE_NAME.to_string(),
+        )
+        .unwrap();
+        self.process(&[pause], &[pauser]).await
+    }
+
+    /// Sets the instance's recovery config with the approval of every admin.
+    pub async fn set_recovery_config(&mut self, recovery: Option<RecoveryConfig>) {
+        let config_epoch = self.config_epoch().await;
+        let set_recovery = SetRecoveryConfigAdmin::build_instruction(
+            &self.program_id,
+            &self.payer(),
+            &self.admin_pubkeys(self.admins.len()),
+            INSTANCE_NAME.to_string(),
+            config_epoch,
+            recovery,
+        )
+        .unwrap();
+        self.process_with_admins(&[set_recovery], self.admins.len())
+            .await
+            .unwrap();
+    }
+
+    /// Starts handing the instance to `admin_config`, signed by `recovery_accounts`.
+    pub async fn start_recovery(
+        &mut self,
+        recovery_accounts: &[&Keypair],
+        admin_config: AdminConfig,
+    ) -> Result<(), BanksClientError> {
+        let recovery_pubkeys: Vec<Pubkey> = recovery_accounts
+            .iter()
+            .map(|recovery_account| recovery_account.pubkey())
+            .collect();
+        let start = StartRecovery::build_instruction(
+            &self.program_id,
+            &self.payer(),
+            &recovery_pubkeys,
+            INSTANCE_NAME.to_string(),
+            admin_config,
+        )
+        .unwrap();
+        self.process(&[start], recovery_accounts).await
+    }
+
+    pub async fn complete_recovery(&mut self) -> Result<(), BanksClientError> {
+        let complete = CompleteRecovery::build_instruction(
+            &self.program_id,
+            &self.payer(),
+            INSTANCE_NAME.to_string(),
+        )
+        .unwrap();
+        self.process(&[complete], &[]).await
+    }
+
+    /// Creates the vault `VAULT_NAME` with the approval of the first `admin_count` admins,
+    /// depositing `lamports` into it.
+    pub async fn create_vault(&mut self, admin_count: usize, lamports: u64) {
+        let payer = self.payer();
+        let create = CreateVault::build_instruction(
+            &self.program_id,
+            &payer,
+            &self.admin_pubkeys(admin_count),
+            INSTANCE_NAME,
+            VAULT_NAME,
+        )
+        .unwrap();
+        let deposit = DepositToVault::build_instruction(
+            &self.program_id,
+            &payer,
+            INSTANCE_NAME,
+            VAULT_NAME,
+            lamports,
+        )
+        .unwrap();
+
+        self.process_with_admins(&[create, deposit], admin_count)
+            .await
+            .unwrap();
+    }
+
+    /// Lets a single admin withdraw `amount` of `mint`, or lamports without one,
+    /// from the vault every `period` seconds, with the approval of every admin.
+    pub async fn set_spending_limit(&mut self, mint: Option<Pubkey>, amount: u64, period: i64) {
+        let config_epoch = self.config_epoch().await;
+        let set_limit = SetSpendingLimit::build_instruction(
+            &self.program_id,
+            &self.payer(),
+            &self.admin_pubkeys(self.admins.len()),
+            INSTANCE_NAME,
+            VAULT_NAME,
+            config_epoch,
+            mint,
+            amount,
+            period,
+        )
+        .unwrap();
+        self.process_with_admins(&[set_limit], self.admins.len())
+            .await
+            .unwrap();
+    }
+
+    /// Creates a mint with 0 decimals whose mint authority is the payer.
+    pub async fn create_mint(&mut self) -> Pubkey {
+        let mint = Keypair::new();
+        let payer = self.payer();
+        let rent = self.context.banks_client.get_rent().await.unwrap();
+
+        let instructions = [
+            system_instruction::create_account(
+                &payer,
+                &mint.pubkey(),
+                rent.minimum_balance(Mint::LEN),
+                Mint::LEN as u64,
+                &spl_token::id(),
+            ),
+            spl_token::instruction::initialize_mint(
+                &spl_token::id(),
+                &mint.pubkey(),
+                &payer,
+                None,
+                0,
+            )
+            .unwrap(),
+        ];
+        self.process(&instructions, &[&mint]).await.unwrap();
+
+        mint.pubkey()
+    }
+
+    /// Creates the associated token account of `owner` for `mint`,
+    /// minting `amount` tokens into it.
+    pub async fn create_token_account(
+        &mut self,
+        owner: &Pubkey,
+        mint: &Pubkey,
+        amount: u64,
+    ) -> Pubkey {
+        let payer = self.payer();
+        let token_account = get_associated_token_address(owner, mint);
+
+        let mut instructions = vec![create_associated_token_account(
+            &payer,
+            owner,
+            mint,
+            &spl_token::id(),
+        )];
+        if amount > 0 {
+            instructions.push(
+                spl_token::instruction::mint_to(
+                    &spl_token::id(),
+                    mint,
+                    &token_account,
+                    &payer,
+                    &[],
+                    amount,
+                )
+                .unwrap(),
+            );
+        }
+        self.process(&instructions, &[]).await.unwrap();
+
+        token_account
+    }
+
+    /// Creates the vault's token account for `mint`, depositing `amount` freshly minted tokens.
+    pub async fn create_vault_token_account(&mut self, mint: &Pubkey, amount: u64) -> Pubkey {
+        let payer = self.payer();
+        let source_token_account = self.create_token_account(&payer, mint, amount).await;
+
+        let create = CreateVaultTokenAccount::build_instruction(
+            &self.program_id,
+            &payer,
+            INSTANCE_NAME,
+            VAULT_NAME,
+            mint,
+        )
+        .unwrap();
+        let deposit = DepositTokensToVault::build_instruction(
+            &self.program_id,
+            &payer,
+            &source_token_account,
+            INSTANCE_NAME,
+            VAULT_NAME,
+            mint,
+            amount,
+        )
+        .unwrap();
+        self.process(&[create, deposit], &[]).await.unwrap();
+
+        get_associated_token_address(&self.vault_pda(), mint)
+    }
+
+    pub async fn token_account(&mut self, pubkey: &Pubkey) -> TokenAccount {
+        TokenAccount::unpack(&self.account_data(pubkey).await).unwrap()
+    }
+
+    pub async fn token_balance(&mut self, pubkey: &Pubkey) -> u64 {
+        self.token_account(pubkey).await.amount
+    }
+
+    pub fn proposal_pda(&self, proposal_name: &str) -> Pubkey {
+        make_proposal_pda(&self.program_id, &self.instance_pda(), proposal_name).0
+    }
+
+    pub async fn proposal(&mut self, proposal_name: &str) -> Proposal {
+        let proposal_pda = self.proposal_pda(proposal_name);
+        Proposal::load(&self.account_data(&proposal_pda).await).unwrap()
+    }
+
+    /// Has the first admin propose `instructions` as `proposal_name`.
+    pub async fn propose(
+        &mut self,
+        proposal_name: &str,
+        instructions: &[Instruction],
+        expiry: Option<Expiry>,
+    ) {
+        let proposer = self.admins[0].insecure_clone();
+        let create = CreateProposal::build_instruction(
+            &self.program_id,
+            &self.payer(),
+            &proposer.pubkey(),
+            INSTANCE_NAME.to_string(),
+            proposal_name.to_string(),
+            instructions,
+            expiry,
+        )
+        .unwrap();
+        self.process(&[create], &[&proposer]).await.unwrap();
+    }
+
+    pub async fn approve(
+        &mut self,
+        proposal_name: &str,
+        admin_index: usize,
+    ) -> Result<(), BanksClientError> {
+        let admin = self.admins[admin_index].insecure_clone();
+        let approve = Approve::build_instruction(
+            &self.program_id,
+            &admin.pubkey(),
+            INSTANCE_NAME.to_string(),
+            proposal_name.to_string(),
+        )
+        .unwrap();
+        self.process(&[approve], &[&admin]).await
+    }
+
+    /// Has the first admin execute `proposal_name`.
+    pub async fn execute(&mut self, proposal_name: &str) -> Result<(), BanksClientError> {
+        let proposal = self.proposal(proposal_name).await;
+        let executor = self.admins[0].insecure_clone();
+        let execute = Execute::build_instruction(
+            &self.program_id,
+            &executor.pubkey(),
+            INSTANCE_NAME.to_string(),
+            proposal_name.to_string(),
+            &proposal,
+        )
+        .unwrap();
+        self.process(&[execute], &[&executor]).await
+    }
+}
+
+/// The custom error code `result` failed with.
+pub fn error_code(result: Result<(), BanksClientError>) -> u32 {
+    match result {
+        Err(BanksClientError::TransactionError(TransactionError::InstructionError(
+            _,
+            InstructionError::Custom(code),
+        ))) => code,
+        other => panic!("expected a custom program error, got {:?}", other),
+    }
+}
+
+pub fn assert_slq_error(result: Result<(), BanksClientError>, expected: SlqError) {
+    assert_eq!(
+        error_code(result),
+        expected as u32,
+        "expected {:?}",
+        expected
+    );
+}
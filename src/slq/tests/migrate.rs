@@ -0,0 +1,58 @@
+mod common;
+
+use common::{assert_slq_error, TestInstance, INSTANCE_NAME};
+use slq::error::SlqError;
+use slq::migrate::MigrateAccount;
+use slq::state::AccountType;
+use slq::vault::{make_vault_pda, CreateVault, SetSpendingLimit};
+
+#[tokio::test]
+async fn vault_the_size_of_a_baseline_multisig_config_is_not_migrated() {
+    let mut test = TestInstance::start(3, 2).await;
+    let payer = test.payer();
+    let mint = test.create_mint().await;
+
+    // A 13 character name and a token spending limit make the vault
+    // exactly as long as a baseline multisig config.
+    let vault_name = "migrate-vault";
+    let create = CreateVault::build_instruction(
+        &test.program_id,
+        &payer,
+        &test.admin_pubkeys(2),
+        INSTANCE_NAME,
+        vault_name,
+    )
+    .unwrap();
+    let set_limit = SetSpendingLimit::build_instruction(
+        &test.program_id,
+        &payer,
+        &test.admin_pubkeys(2),
+        INSTANCE_NAME,
+        vault_name,
+        0,
+        Some(mint),
+        100,
+        3600,
+    )
+    .unwrap();
+    test.process_with_admins(&[create, set_limit], 2)
+        .await
+        .unwrap();
+
+    let (vault_pda, _) = make_vault_pda(&test.program_id, &test.instance_pda(), vault_name);
+    let data = test.account_data(&vault_pda).await;
+    assert_eq!(data.len(), 1 + 32 * 5);
+
+    let migrate = MigrateAccount::build_instruction(
+        &test.program_id,
+        &payer,
+        &vault_pda,
+        AccountType::MultisigConfig,
+        vault_name,
+    )
+    .unwrap();
+    let result = test.process(&[migrate], &[]).await;
+    assert_slq_error(result, SlqError::WrongAccountType);
+
+    assert_eq!(test.account_data(&vault_pda).await, data);
+}
@@ -0,0 +1,188 @@
+mod common;
+
+use solana_program::instruction::Instruction;
+use solana_sdk::signature::{Keypair, Signer};
+
+use common::{assert_slq_error, TestInstance, INSTANCE_NAME};
+use slq::admin::ChangeApprovalThresholdAdmin;
+use slq::error::SlqError;
+use slq::proposal::Cancel;
+use slq::state::Expiry;
+
+const PROPOSAL_NAME: &str = "test-proposal";
+
+/// Raises the approval threshold to 3 once invoked by the instance's authority.
+async fn change_approval_threshold(test: &mut TestInstance) -> Instruction {
+    let config_epoch = test.config_epoch().await;
+    ChangeApprovalThresholdAdmin::build_instruction(
+        &test.program_id,
+        &test.payer(),
+        &[test.authority_pda()],
+        INSTANCE_NAME.to_string(),
+        config_epoch,
+        3,
+    )
+    .unwrap()
+}
+
+/// A 2 of 3 instance with `PROPOSAL_NAME` proposed and not yet approved.
+async fn start() -> TestInstance {
+    start_with_execution_delay(0).await
+}
+
+/// Like `start`, with `execution_delay` set before proposing.
+async fn start_with_execution_delay(execution_delay: i64) -> TestInstance {
+    let mut test = TestInstance::start(3, 2).await;
+    if execution_delay > 0 {
+        test.set_execution_delay(execution_delay).await;
+    }
+    let change = change_approval_threshold(&mut test).await;
+    test.propose(PROPOSAL_NAME, &[change], None).await;
+    test
+}
+
+#[tokio::test]
+async fn proposal_executes_once_the_threshold_is_met() {
+    let mut test = start().await;
+
+    test.approve(PROPOSAL_NAME, 0).await.unwrap();
+    test.approve(PROPOSAL_NAME, 1).await.unwrap();
+    let proposal_pda = test.proposal_pda(PROPOSAL_NAME);
+    let proposal_rent = test.lamports(&proposal_pda).await;
+    test.execute(PROPOSAL_NAME).await.unwrap();
+
+    assert!(!test.account_exists(&proposal_pda).await);
+    let proposer = test.admins[0].pubkey();
+    assert_eq!(test.lamports(&proposer).await, proposal_rent);
+    let instance = test.instance().await;
+    assert_eq!(instance.admin_config.approval_threshold, 3);
+    assert_eq!(instance.open_proposals, 0);
+}
+
+#[tokio::test]
+async fn proposal_below_the_threshold_is_not_executed() {
+    let mut test = start().await;
+
+    test.approve(PROPOSAL_NAME, 0).await.unwrap();
+    let result = test.execute(PROPOSAL_NAME).await;
+    assert_slq_error(result, SlqError::ApprovalThresholdNotMet);
+}
+
+#[tokio::test]
+async fn admin_approving_twice_is_refused() {
+    let mut test = start().await;
+
+    test.approve(PROPOSAL_NAME, 0).await.unwrap();
+    let result = test.approve(PROPOSAL_NAME, 0).await;
+    assert_slq_error(result, SlqError::AlreadyApproved);
+}
+
+#[tokio::test]
+async fn proposal_from_an_earlier_config_epoch_is_stale() {
+    let mut test = start().await;
+    test.approve(PROPOSAL_NAME, 0).await.unwrap();
+
+    test.set_execution_delay(0).await;
+
+    let result = test.approve(PROPOSAL_NAME, 1).await;
+    assert_slq_error(result, SlqError::StaleProposal);
+}
+
+#[tokio::test]
+async fn expired_proposal_is_refused() {
+    let mut test = TestInstance::start(3, 2).await;
+    let now = test.now().await;
+    let change = change_approval_threshold(&mut test).await;
+    let expiry = Expiry::UnixTimestamp(now + 10);
+    test.propose(PROPOSAL_NAME, &[change], Some(expiry)).await;
+    test.approve(PROPOSAL_NAME, 0).await.unwrap();
+
+    test.warp_forward(11).await;
+
+    let result = test.approve(PROPOSAL_NAME, 1).await;
+    assert_slq_error(result, SlqError::ProposalExpired);
+}
+
+#[tokio::test]
+async fn proposal_waits_out_the_execution_delay() {
+    let mut test = start_with_execution_delay(100).await;
+    test.approve(PROPOSAL_NAME, 0).await.unwrap();
+    test.approve(PROPOSAL_NAME, 1).await.unwrap();
+
+    let result = test.execute(PROPOSAL_NAME).await;
+    assert_slq_error(result, SlqError::TimelockNotElapsed);
+
+    test.warp_forward(99).await;
+    let result = test.execute(PROPOSAL_NAME).await;
+    assert_slq_error(result, SlqError::TimelockNotElapsed);
+
+    test.warp_forward(1).await;
+    test.execute(PROPOSAL_NAME).await.unwrap();
+    assert_eq!(test.instance().await.admin_config.approval_threshold, 3);
+}
+
+#[tokio::test]
+async fn proposal_is_cancelled_during_the_execution_delay() {
+    let mut test = start_with_execution_delay(100).await;
+    test.approve(PROPOSAL_NAME, 0).await.unwrap();
+    test.approve(PROPOSAL_NAME, 1).await.unwrap();
+
+    let canceller = test.admins[2].insecure_clone();
+    let cancel = Cancel::build_instruction(
+        &test.program_id,
+        &canceller.pubkey(),
+        INSTANCE_NAME.to_string(),
+        PROPOSAL_NAME.to_string(),
+        &test.payer(),
+    )
+    .unwrap();
+    test.process(&[cancel], &[&canceller]).await.unwrap();
+
+    let proposal_pda = test.proposal_pda(PROPOSAL_NAME);
+    assert!(!test.account_exists(&proposal_pda).await);
+    assert_eq!(test.instance().await.open_proposals, 0);
+}
+
+#[tokio::test]
+async fn guardian_outside_the_admins_cancels_a_proposal() {
+    let mut test = TestInstance::start(3, 2).await;
+    let guardian = Keypair::new();
+    test.set_guardians(vec![guardian.pubkey()]).await;
+    let change = change_approval_threshold(&mut test).await;
+    test.propose(PROPOSAL_NAME, &[change], None).await;
+    let last_admin_action = test.instance().await.last_admin_action;
+    test.warp_forward(10).await;
+
+    let cancel = Cancel::build_instruction(
+        &test.program_id,
+        &guardian.pubkey(),
+        INSTANCE_NAME.to_string(),
+        PROPOSAL_NAME.to_string(),
+        &test.payer(),
+    )
+    .unwrap();
+    test.process(&[cancel], &[&guardian]).await.unwrap();
+
+    let proposal_pda = test.proposal_pda(PROPOSAL_NAME);
+    assert!(!test.account_exists(&proposal_pda).await);
+    let instance = test.instance().await;
+    assert_eq!(instance.open_proposals, 0);
+    assert_eq!(instance.last_admin_action, last_admin_action);
+}
+
+#[tokio::test]
+async fn signer_neither_guardian_nor_admin_cannot_cancel() {
+    let mut test = start().await;
+    let outsider = Keypair::new();
+
+    let cancel = Cancel::build_instruction(
+        &test.program_id,
+        &outsider.pubkey(),
+        INSTANCE_NAME.to_string(),
+        PROPOSAL_NAME.to_string(),
+        &test.payer(),
+    )
+    .unwrap();
+    let result = test.process(&[cancel], &[&outsider]).await;
+    assert_slq_error(result, SlqError::NotAnAdmin);
+}
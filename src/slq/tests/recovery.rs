@@ -0,0 +1,152 @@
+mod common;
+
+use solana_program::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signer};
+
+use common::{assert_slq_error, TestInstance, INSTANCE_NAME};
+use slq::error::SlqError;
+use slq::recovery::VetoRecovery;
+use slq::state::{AdminAccount, AdminConfig, AdminKind, RecoveryConfig, Roles};
+
+const INACTIVITY_PERIOD: i64 = 1000;
+const TIMELOCK: i64 = 500;
+
+/// A 2 of 3 instance that 2 of the 3 returned recovery accounts may recover.
+async fn start() -> (TestInstance, Vec<Keypair>) {
+    let mut test = TestInstance::start(3, 2).await;
+    let recovery_accounts: Vec<Keypair> = (0..3).map(|_| Keypair::new()).collect();
+
+    test.set_recovery_config(Some(RecoveryConfig {
+        recovery_accounts: recovery_accounts.iter().map(Keypair::pubkey).collect(),
+        threshold: 2,
+        inactivity_period: INACTIVITY_PERIOD,
+        timelock: TIMELOCK,
+    }))
+    .await;
+
+    (test, recovery_accounts)
+}
+
+/// An admin config of `new_admin` alone.
+fn recovered_config(new_admin: Pubkey) -> AdminConfig {
+    AdminConfig {
+        approval_threshold: 1,
+        max_admin_accounts: 8,
+        admin_accounts: vec![AdminAccount {
+            pubkey: new_admin,
+            weight: 1,
+            roles: Roles::ALL,
+            kind: AdminKind::Key,
+        }],
+        policy: Default::default(),
+    }
+}
+
+#[tokio::test]
+async fn recovery_waits_for_the_admins_to_go_inactive() {
+    let (mut test, recovery_accounts) = start().await;
+
+    test.warp_forward(INACTIVITY_PERIOD - 1).await;
+    let signers = [&recovery_accounts[0], &recovery_accounts[1]];
+    let result = test
+        .start_recovery(&signers, recovered_config(Pubkey::new_unique()))
+        .await;
+    assert_slq_error(result, SlqError::AdminsStillActive);
+}
+
+#[tokio::test]
+async fn recovery_needs_the_recovery_threshold() {
+    let (mut test, recovery_accounts) = start().await;
+
+    test.warp_forward(INACTIVITY_PERIOD).await;
+    let result = test
+        .start_recovery(
+            &[&recovery_accounts[0]],
+            recovered_config(Pubkey::new_unique()),
+        )
+        .await;
+    assert_slq_error(result, SlqError::ApprovalThresholdNotMet);
+}
+
+#[tokio::test]
+async fn recovery_completes_after_its_timelock() {
+    let (mut test, recovery_accounts) = start().await;
+    let config_epoch = test.config_epoch().await;
+    let new_admin = Pubkey::new_unique();
+
+    test.warp_forward(INACTIVITY_PERIOD).await;
+    let signers = [&recovery_accounts[0], &recovery_accounts[1]];
+    test.start_recovery(&signers, recovered_config(new_admin))
+        .await
+        .unwrap();
+
+    test.warp_forward(TIMELOCK - 1).await;
+    let result = test.complete_recovery().await;
+    assert_slq_error(result, SlqError::TimelockNotElapsed);
+
+    test.warp_forward(1).await;
+    test.complete_recovery().await.unwrap();
+
+    let instance = test.instance().await;
+    assert!(instance.pending_recovery.is_none());
+    assert_eq!(instance.config_epoch, config_epoch + 1);
+    let admin_pubkeys: Vec<Pubkey> = instance
+        .admin_config
+        .admin_accounts
+        .iter()
+        .map(|admin_account| admin_account.pubkey)
+        .collect();
+    assert_eq!(admin_pubkeys, [new_admin]);
+}
+
+#[tokio::test]
+async fn admin_vetoes_a_pending_recovery() {
+    let (mut test, recovery_accounts) = start().await;
+
+    test.warp_forward(INACTIVITY_PERIOD).await;
+    let signers = [&recovery_accounts[0], &recovery_accounts[1]];
+    test.start_recovery(&signers, recovered_config(Pubkey::new_unique()))
+        .await
+        .unwrap();
+
+    let admin = test.admins[2].insecure_clone();
+    test.fund(&admin.pubkey(), 1_000_000_000).await;
+    let veto = VetoRecovery::build_instruction(
+        &test.program_id,
+        &admin.pubkey(),
+        INSTANCE_NAME.to_string(),
+    )
+    .unwrap();
+    test.process(&[veto], &[&admin]).await.unwrap();
+
+    let instance = test.instance().await;
+    assert!(instance.pending_recovery.is_none());
+    assert_eq!(instance.admin_config.admin_accounts.len(), 3);
+
+    test.warp_forward(TIMELOCK).await;
+    let result = test.complete_recovery().await;
+    assert_slq_error(result, SlqError::NoPendingRecovery);
+
+    // The veto counts as admin activity, restarting the inactivity period.
+    let result = test
+        .start_recovery(&signers, recovered_config(Pubkey::new_unique()))
+        .await;
+    assert_slq_error(result, SlqError::AdminsStillActive);
+}
+
+#[tokio::test]
+async fn pause_by_a_guardian_outside_the_admins_does_not_delay_recovery() {
+    let (mut test, recovery_accounts) = start().await;
+    let guardian = Keypair::new();
+    test.set_guardians(vec![guardian.pubkey()]).await;
+
+    test.warp_forward(INACTIVITY_PERIOD / 2).await;
+    test.pause(&guardian).await.unwrap();
+    test.warp_forward(INACTIVITY_PERIOD / 2).await;
+
+    let signers = [&recovery_accounts[0], &recovery_accounts[1]];
+    test.start_recovery(&signers, recovered_config(Pubkey::new_unique()))
+        .await
+        .unwrap();
+    assert!(test.instance().await.pending_recovery.is_some());
+}
@@ -0,0 +1,265 @@
+mod common;
+
+use solana_program::instruction::Instruction;
+use solana_program::pubkey::Pubkey;
+
+use common::{assert_slq_error, TestInstance, INSTANCE_NAME, VAULT_NAME};
+use slq::admin::DestroyInstanceAdmin;
+use slq::error::SlqError;
+use slq::state::SpendingLimit;
+use slq::vault::{CloseVault, SetSpendingLimit, WithdrawFromVault};
+
+/// A 2 of 3 instance with a vault holding 10 SOL above its rent exemption.
+async fn start() -> TestInstance {
+    let mut test = TestInstance::start(3, 2).await;
+    test.create_vault(2, 10_000_000_000).await;
+    test
+}
+
+fn withdraw(
+    test: &TestInstance,
+    admin_pubkeys: &[Pubkey],
+    recipient: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    WithdrawFromVault::build_instruction(
+        &test.program_id,
+        admin_pubkeys,
+        INSTANCE_NAME,
+        VAULT_NAME,
+        recipient,
+        amount,
+    )
+    .unwrap()
+}
+
+#[tokio::test]
+async fn withdraws_to_any_recipient_with_the_threshold() {
+    let mut test = start().await;
+    let recipient = Pubkey::new_unique();
+
+    let withdraw = withdraw(&test, &test.admin_pubkeys(2), &recipient, 3_000_000_000);
+    test.process_with_admins(&[withdraw], 2).await.unwrap();
+
+    assert_eq!(test.lamports(&recipient).await, 3_000_000_000);
+    assert_eq!(test.vault().await.instance, test.instance_pda());
+}
+
+#[tokio::test]
+async fn withdraws_through_an_executed_proposal() {
+    let mut test = start().await;
+    let recipient = Pubkey::new_unique();
+
+    let withdraw = withdraw(&test, &[test.authority_pda()], &recipient, 3_000_000_000);
+    test.propose("withdraw", &[withdraw], None).await;
+    test.approve("withdraw", 0).await.unwrap();
+    test.approve("withdraw", 1).await.unwrap();
+    test.execute("withdraw").await.unwrap();
+
+    assert_eq!(test.lamports(&recipient).await, 3_000_000_000);
+}
+
+#[tokio::test]
+async fn withdraw_below_the_threshold_is_refused() {
+    let mut test = start().await;
+
+    let withdraw = withdraw(
+        &test,
+        &test.admin_pubkeys(1),
+        &Pubkey::new_unique(),
+        1_000_000_000,
+    );
+    let result = test.process_with_admins(&[withdraw], 1).await;
+    assert_slq_error(result, SlqError::ApprovalThresholdNotMet);
+}
+
+#[tokio::test]
+async fn admin_signing_a_withdrawal_twice_counts_once() {
+    let mut test = start().await;
+
+    let admin = test.admin_pubkeys(1)[0];
+    let withdraw = withdraw(&test, &[admin, admin], &Pubkey::new_unique(), 1_000_000_000);
+    let result = test.process_with_admins(&[withdraw], 1).await;
+    assert_slq_error(result, SlqError::DuplicateAdminSigner);
+}
+
+#[tokio::test]
+async fn withdraw_keeps_the_vaults_rent_exemption() {
+    let mut test = start().await;
+
+    let withdraw = withdraw(
+        &test,
+        &test.admin_pubkeys(2),
+        &Pubkey::new_unique(),
+        10_000_000_001,
+    );
+    let result = test.process_with_admins(&[withdraw], 2).await;
+    assert_slq_error(result, SlqError::InsufficientFunds);
+}
+
+#[tokio::test]
+async fn withdraw_is_refused_while_paused() {
+    let mut test = start().await;
+    let guardian = test.admins[0].insecure_clone();
+    test.pause(&guardian).await.unwrap();
+
+    let withdraw = withdraw(
+        &test,
+        &test.admin_pubkeys(2),
+        &Pubkey::new_unique(),
+        1_000_000_000,
+    );
+    let result = test.process_with_admins(&[withdraw], 2).await;
+    assert_slq_error(result, SlqError::InstancePaused);
+}
+
+#[tokio::test]
+async fn single_admin_withdraws_within_the_spending_limit() {
+    let mut test = start().await;
+    test.set_spending_limit(None, 2_000_000_000, 3600).await;
+    let recipient = Pubkey::new_unique();
+
+    let first = withdraw(&test, &test.admin_pubkeys(1), &recipient, 1_500_000_000);
+    test.process_with_admins(&[first], 1).await.unwrap();
+
+    let second = withdraw(&test, &test.admin_pubkeys(1), &recipient, 1_000_000_000);
+    let result = test
+        .process_with_admins(std::slice::from_ref(&second), 1)
+        .await;
+    assert_slq_error(result, SlqError::ApprovalThresholdNotMet);
+
+    test.warp_forward(3600).await;
+    test.process_with_admins(&[second], 1).await.unwrap();
+    assert_eq!(test.lamports(&recipient).await, 2_500_000_000);
+}
+
+#[tokio::test]
+async fn spending_limit_does_not_skip_the_execution_delay() {
+    let mut test = start().await;
+    test.set_spending_limit(None, 2_000_000_000, 3600).await;
+    test.set_execution_delay(100).await;
+
+    let withdraw = withdraw(
+        &test,
+        &test.admin_pubkeys(1),
+        &Pubkey::new_unique(),
+        1_000_000_000,
+    );
+    let result = test.process_with_admins(&[withdraw], 1).await;
+    assert_slq_error(result, SlqError::TimelockRequired);
+}
+
+#[tokio::test]
+async fn set_spending_limit_records_an_admin_action() {
+    let mut test = start().await;
+    test.warp_forward(10).await;
+
+    test.set_spending_limit(None, 2_000_000_000, 3600).await;
+
+    let now = test.now().await;
+    assert_eq!(test.instance().await.last_admin_action, now);
+}
+
+#[tokio::test]
+async fn set_spending_limit_built_before_a_config_change_is_refused() {
+    let mut test = start().await;
+
+    let set_limit = SetSpendingLimit::build_instruction(
+        &test.program_id,
+        &test.payer(),
+        &test.admin_pubkeys(2),
+        INSTANCE_NAME,
+        VAULT_NAME,
+        0,
+        None,
+        2_000_000_000,
+        3600,
+    )
+    .unwrap();
+    test.set_execution_delay(0).await;
+
+    let result = test.process_with_admins(&[set_limit], 2).await;
+    assert_slq_error(result, SlqError::ConfigChanged);
+}
+
+#[tokio::test]
+async fn closing_the_vault_lets_the_instance_be_destroyed() {
+    let mut test = start().await;
+    let recipient = Pubkey::new_unique();
+    let vault_lamports = test.lamports(&test.vault_pda()).await;
+
+    let config_epoch = test.config_epoch().await;
+    let destroy = DestroyInstanceAdmin::build_instruction(
+        &test.program_id,
+        &test.payer(),
+        &test.admin_pubkeys(2),
+        INSTANCE_NAME.to_string(),
+        config_epoch,
+        &recipient,
+    )
+    .unwrap();
+    let result = test
+        .process_with_admins(std::slice::from_ref(&destroy), 2)
+        .await;
+    assert_slq_error(result, SlqError::InstanceInUse);
+
+    let config_epoch = test.config_epoch().await;
+    let close = CloseVault::build_instruction(
+        &test.program_id,
+        &test.admin_pubkeys(2),
+        INSTANCE_NAME,
+        VAULT_NAME,
+        config_epoch,
+        &recipient,
+        &[],
+    )
+    .unwrap();
+    test.process_with_admins(&[close], 2).await.unwrap();
+
+    assert!(!test.account_exists(&test.vault_pda()).await);
+    assert_eq!(test.lamports(&recipient).await, vault_lamports);
+    assert_eq!(test.instance().await.vaults, 0);
+
+    test.process_with_admins(&[destroy], 2).await.unwrap();
+    assert!(!test.account_exists(&test.instance_pda()).await);
+}
+
+#[tokio::test]
+async fn close_vault_below_the_threshold_is_refused() {
+    let mut test = start().await;
+
+    let config_epoch = test.config_epoch().await;
+    let close = CloseVault::build_instruction(
+        &test.program_id,
+        &test.admin_pubkeys(1),
+        INSTANCE_NAME,
+        VAULT_NAME,
+        config_epoch,
+        &Pubkey::new_unique(),
+        &[],
+    )
+    .unwrap();
+    let result = test.process_with_admins(&[close], 1).await;
+    assert_slq_error(result, SlqError::ApprovalThresholdNotMet);
+    assert_eq!(test.instance().await.vaults, 1);
+}
+
+#[test]
+fn spending_limit_periods_stay_aligned() {
+    let mut spending_limit = SpendingLimit {
+        mint: None,
+        amount: 100,
+        period: 60,
+        period_start: 0,
+        spent: 0,
+    };
+
+    assert!(spending_limit.try_spend(50, 100));
+    assert!(!spending_limit.try_spend(59, 1));
+
+    // A withdrawal late in a period doesn't push back the start of the next.
+    assert!(spending_limit.try_spend(170, 100));
+    assert_eq!(spending_limit.period_start, 120);
+    assert!(spending_limit.try_spend(180, 100));
+    assert_eq!(spending_limit.period_start, 180);
+}
@@ -0,0 +1,276 @@
+mod common;
+
+use solana_program::instruction::Instruction;
+use solana_program::pubkey::Pubkey;
+use spl_associated_token_account::get_associated_token_address;
+use spl_token::error::TokenError;
+
+use common::{assert_slq_error, error_code, TestInstance, INSTANCE_NAME, VAULT_NAME};
+use slq::error::SlqError;
+use slq::vault::{
+    CloseVault, CreateVaultTokenAccount, DepositTokensToVault, WithdrawTokensFromVault,
+};
+
+/// A 2 of 3 instance with a vault holding 1000 tokens of the returned mint.
+async fn start() -> (TestInstance, Pubkey) {
+    let mut test = TestInstance::start(3, 2).await;
+    test.create_vault(2, 1_000_000).await;
+    let mint = test.create_mint().await;
+    test.create_vault_token_account(&mint, 1000).await;
+    (test, mint)
+}
+
+fn withdraw(
+    test: &TestInstance,
+    admin_count: usize,
+    mint: &Pubkey,
+    recipient_token_account: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    WithdrawTokensFromVault::build_instruction(
+        &test.program_id,
+        &test.admin_pubkeys(admin_count),
+        INSTANCE_NAME,
+        VAULT_NAME,
+        mint,
+        recipient_token_account,
+        amount,
+    )
+    .unwrap()
+}
+
+#[tokio::test]
+async fn deposits_into_the_vaults_token_account() {
+    let (mut test, mint) = start().await;
+
+    let vault_token_account = get_associated_token_address(&test.vault_pda(), &mint);
+    let token_account = test.token_account(&vault_token_account).await;
+    assert_eq!(token_account.owner, test.vault_pda());
+    assert_eq!(token_account.mint, mint);
+    assert_eq!(token_account.amount, 1000);
+}
+
+#[tokio::test]
+async fn create_vault_token_account_refuses_another_address() {
+    let mut test = TestInstance::start(3, 2).await;
+    test.create_vault(2, 1_000_000).await;
+    let mint = test.create_mint().await;
+    let payer = test.payer();
+
+    let mut create = CreateVaultTokenAccount::build_instruction(
+        &test.program_id,
+        &payer,
+        INSTANCE_NAME,
+        VAULT_NAME,
+        &mint,
+    )
+    .unwrap();
+    // The payer's associated token account rather than the vault's.
+    create.accounts[2].pubkey = get_associated_token_address(&payer, &mint);
+
+    let result = test.process(&[create], &[]).await;
+    assert_slq_error(result, SlqError::WrongTokenAccount);
+}
+
+#[tokio::test]
+async fn lamports_sent_ahead_do_not_block_creating_the_vault_token_account() {
+    let mut test = TestInstance::start(3, 2).await;
+    test.create_vault(2, 1_000_000).await;
+    let mint = test.create_mint().await;
+    let vault_token_account = get_associated_token_address(&test.vault_pda(), &mint);
+    let rent = test.context.banks_client.get_rent().await.unwrap();
+    test.fund(&vault_token_account, rent.minimum_balance(0))
+        .await;
+
+    test.create_vault_token_account(&mint, 1000).await;
+
+    assert_eq!(test.token_balance(&vault_token_account).await, 1000);
+}
+
+#[tokio::test]
+async fn withdraws_with_the_threshold() {
+    let (mut test, mint) = start().await;
+    let recipient = test
+        .create_token_account(&Pubkey::new_unique(), &mint, 0)
+        .await;
+
+    let withdraw = withdraw(&test, 2, &mint, &recipient, 300);
+    test.process_with_admins(&[withdraw], 2).await.unwrap();
+
+    let vault_token_account = get_associated_token_address(&test.vault_pda(), &mint);
+    assert_eq!(test.token_balance(&vault_token_account).await, 700);
+    assert_eq!(test.token_balance(&recipient).await, 300);
+}
+
+#[tokio::test]
+async fn withdraw_below_the_threshold_is_refused() {
+    let (mut test, mint) = start().await;
+    let recipient = test
+        .create_token_account(&Pubkey::new_unique(), &mint, 0)
+        .await;
+
+    let withdraw = withdraw(&test, 1, &mint, &recipient, 300);
+    let result = test.process_with_admins(&[withdraw], 1).await;
+    assert_slq_error(result, SlqError::ApprovalThresholdNotMet);
+}
+
+#[tokio::test]
+async fn withdraw_refuses_a_token_account_the_vault_does_not_own() {
+    let (mut test, mint) = start().await;
+    let recipient = test
+        .create_token_account(&Pubkey::new_unique(), &mint, 0)
+        .await;
+
+    let mut withdraw = withdraw(&test, 2, &mint, &recipient, 300);
+    withdraw.accounts[2].pubkey = get_associated_token_address(&test.payer(), &mint);
+
+    let result = test.process_with_admins(&[withdraw], 2).await;
+    assert_slq_error(result, SlqError::WrongTokenAccount);
+}
+
+#[tokio::test]
+async fn deposit_refuses_a_token_account_the_vault_does_not_own() {
+    let (mut test, mint) = start().await;
+    let payer = test.payer();
+    let other_token_account = test
+        .create_token_account(&Pubkey::new_unique(), &mint, 0)
+        .await;
+
+    let mut deposit = DepositTokensToVault::build_instruction(
+        &test.program_id,
+        &payer,
+        &get_associated_token_address(&payer, &mint),
+        INSTANCE_NAME,
+        VAULT_NAME,
+        &mint,
+        100,
+    )
+    .unwrap();
+    deposit.accounts[3].pubkey = other_token_account;
+
+    let result = test.process(&[deposit], &[]).await;
+    assert_slq_error(result, SlqError::WrongTokenAccount);
+}
+
+#[tokio::test]
+async fn deposit_refuses_tokens_of_another_mint() {
+    let (mut test, mint) = start().await;
+    let payer = test.payer();
+    let other_mint = test.create_mint().await;
+    let other_source = test.create_token_account(&payer, &other_mint, 100).await;
+
+    let deposit = DepositTokensToVault::build_instruction(
+        &test.program_id,
+        &payer,
+        &other_source,
+        INSTANCE_NAME,
+        VAULT_NAME,
+        &mint,
+        100,
+    )
+    .unwrap();
+
+    let result = test.process(&[deposit], &[]).await;
+    assert_eq!(error_code(result), TokenError::MintMismatch as u32);
+}
+
+#[tokio::test]
+async fn withdraw_refuses_a_recipient_of_another_mint() {
+    let (mut test, mint) = start().await;
+    let other_mint = test.create_mint().await;
+    let recipient = test
+        .create_token_account(&Pubkey::new_unique(), &other_mint, 0)
+        .await;
+
+    let withdraw = withdraw(&test, 2, &mint, &recipient, 300);
+    let result = test.process_with_admins(&[withdraw], 2).await;
+    assert_eq!(error_code(result), TokenError::MintMismatch as u32);
+}
+
+#[tokio::test]
+async fn withdraw_is_refused_while_paused() {
+    let (mut test, mint) = start().await;
+    let recipient = test
+        .create_token_account(&Pubkey::new_unique(), &mint, 0)
+        .await;
+    let guardian = test.admins[0].insecure_clone();
+    test.pause(&guardian).await.unwrap();
+
+    let withdraw = withdraw(&test, 2, &mint, &recipient, 300);
+    let result = test.process_with_admins(&[withdraw], 2).await;
+    assert_slq_error(result, SlqError::InstancePaused);
+}
+
+#[tokio::test]
+async fn single_admin_withdraws_within_the_spending_limit() {
+    let (mut test, mint) = start().await;
+    let recipient = test
+        .create_token_account(&Pubkey::new_unique(), &mint, 0)
+        .await;
+    test.set_spending_limit(Some(mint), 100, 3600).await;
+
+    let first = withdraw(&test, 1, &mint, &recipient, 60);
+    test.process_with_admins(&[first], 1).await.unwrap();
+    assert_eq!(test.token_balance(&recipient).await, 60);
+
+    let second = withdraw(&test, 1, &mint, &recipient, 60);
+    let result = test
+        .process_with_admins(std::slice::from_ref(&second), 1)
+        .await;
+    assert_slq_error(result, SlqError::ApprovalThresholdNotMet);
+
+    test.warp_forward(3600).await;
+    test.process_with_admins(&[second], 1).await.unwrap();
+    assert_eq!(test.token_balance(&recipient).await, 120);
+}
+
+#[tokio::test]
+async fn close_vault_refuses_a_token_account_holding_tokens() {
+    let (mut test, mint) = start().await;
+
+    let config_epoch = test.config_epoch().await;
+    let close = CloseVault::build_instruction(
+        &test.program_id,
+        &test.admin_pubkeys(2),
+        INSTANCE_NAME,
+        VAULT_NAME,
+        config_epoch,
+        &Pubkey::new_unique(),
+        &[mint],
+    )
+    .unwrap();
+    let result = test.process_with_admins(&[close], 2).await;
+    assert_slq_error(result, SlqError::VaultNotEmpty);
+}
+
+#[tokio::test]
+async fn close_vault_closes_its_empty_token_accounts() {
+    let (mut test, mint) = start().await;
+    let recipient_token_account = test
+        .create_token_account(&Pubkey::new_unique(), &mint, 0)
+        .await;
+    let withdraw = withdraw(&test, 2, &mint, &recipient_token_account, 1000);
+    test.process_with_admins(&[withdraw], 2).await.unwrap();
+
+    let recipient = Pubkey::new_unique();
+    let vault_token_account = get_associated_token_address(&test.vault_pda(), &mint);
+    let lamports =
+        test.lamports(&test.vault_pda()).await + test.lamports(&vault_token_account).await;
+
+    let config_epoch = test.config_epoch().await;
+    let close = CloseVault::build_instruction(
+        &test.program_id,
+        &test.admin_pubkeys(2),
+        INSTANCE_NAME,
+        VAULT_NAME,
+        config_epoch,
+        &recipient,
+        &[mint],
+    )
+    .unwrap();
+    test.process_with_admins(&[close], 2).await.unwrap();
+
+    assert!(!test.account_exists(&test.vault_pda()).await);
+    assert!(!test.account_exists(&vault_token_account).await);
+    assert_eq!(test.lamports(&recipient).await, lamports);
+}